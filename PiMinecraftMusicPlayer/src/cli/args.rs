@@ -0,0 +1,93 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Plays Minecraft music picked from the weather, time and other external factors")]
+pub struct Cli {
+    /// Path to the TOML config file
+    #[arg(long, global = true, env = "MUSIC_PLAYER_CONFIG", default_value = "config.toml")]
+    pub config: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Sample today's play times and install them with cron or systemd timers
+    Schedule {
+        #[command(subcommand)]
+        command: Option<ScheduleCommand>,
+    },
+    /// Stay running and play at sampled times, re-planning every midnight, without cron
+    Daemon,
+    /// Pick a song for the current conditions and play it
+    Play,
+    /// Pick a song without playing or recording it
+    Pick {
+        /// Kept for older scripts, picks are never recorded since they aren't played
+        #[arg(long, hide = true)]
+        dry_run: bool,
+    },
+    /// Show why a song would be chosen right now, without playing or recording it
+    Explain {
+        /// Print the explanation as JSON
+        #[arg(long)]
+        json: bool,
+        /// Show the explanation recorded for the last real play instead
+        #[arg(long)]
+        last: bool,
+    },
+    /// Show the most recent song selections
+    History {
+        /// Number of selections to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Fetch every external factor and show its value and mood contribution
+    Factors,
+    /// List the track catalog and any files or ratings that could not be matched
+    Library,
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScheduleCommand {
+    /// Print the times `schedule` would install for a date, without installing anything
+    Preview {
+        /// Day to plan, as YYYY-MM-DD (defaults to today)
+        #[arg(long)]
+        date: Option<NaiveDate>,
+        /// Seed for the random draws, to get the same times again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Sample many days and print an hourly histogram and quiet hour violations
+    Simulate {
+        /// Number of days to sample, starting today
+        #[arg(long, default_value_t = 365)]
+        days: u32,
+        /// Seed for the random draws, to get the same results again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the resolved config and every problem found
+    Check,
+}
+
+// Crontabs still call `MinecraftMusicPlayer.sh 0` to schedule and `... 1` to play
+pub fn translate_legacy_args(mut args: Vec<String>) -> Vec<String> {
+    if let Some(mode) = args.get(1).and_then(|a| a.parse::<i32>().ok()) {
+        args[1] = if mode == 0 { "schedule" } else { "play" }.to_string();
+    }
+    args
+}
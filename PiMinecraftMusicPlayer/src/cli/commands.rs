@@ -0,0 +1,174 @@
+use super::args::{Command, ConfigCommand, ScheduleCommand};
+use crate::config::{check_config, get_config};
+use crate::external_factors::get_external_factors;
+use crate::player::catalog::load_catalog;
+use crate::player::explain::Explanation;
+use crate::player::play_song;
+use crate::player::song_picker::{get_best_song, map_factors_to_mood, print_factors};
+use crate::scheduler::daemon::run_daemon;
+use crate::scheduler::preview::{preview, simulate};
+use crate::scheduler::scheduler::schedule_plays;
+use std::fs;
+use std::path::Path;
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_CONFIG: i32 = 3;
+
+fn exit_code(success: bool) -> i32 {
+    if success { EXIT_OK } else { EXIT_FAILURE }
+}
+
+pub async fn run(command: Command, config_path: &Path) -> i32 {
+    match command {
+        Command::Schedule { command: None } => schedule(),
+        Command::Schedule {
+            command: Some(ScheduleCommand::Preview { date, seed }),
+        } => exit_code(preview(date, seed)),
+        Command::Schedule {
+            command: Some(ScheduleCommand::Simulate { days, seed }),
+        } => exit_code(simulate(days, seed)),
+        Command::Daemon => daemon().await,
+        Command::Play => play().await,
+        Command::Pick { .. } => pick().await,
+        Command::Explain { json, last } => explain(json, last).await,
+        Command::History { limit } => history(limit),
+        Command::Factors => factors().await,
+        Command::Library => library(),
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => config_check(config_path),
+    }
+}
+
+pub fn config_check(config_path: &Path) -> i32 {
+    if check_config(config_path) { EXIT_OK } else { EXIT_CONFIG }
+}
+
+pub fn schedule() -> i32 {
+    exit_code(schedule_plays())
+}
+
+pub async fn daemon() -> i32 {
+    exit_code(run_daemon().await)
+}
+
+pub async fn play() -> i32 {
+    exit_code(play_song().await)
+}
+
+// Only `play` records, a track that was never heard shouldn't count as recently played
+pub async fn pick() -> i32 {
+    let catalog = match load_catalog() {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            eprintln!("Failed to load song catalog: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    match get_best_song(&catalog).await {
+        Some(pick) => {
+            println!("{}", pick.track.path.display());
+            EXIT_OK
+        }
+        None => EXIT_FAILURE,
+    }
+}
+
+pub async fn explain(json: bool, last: bool) -> i32 {
+    let explanation = if last {
+        match load_last_explanation() {
+            Ok(explanation) => explanation,
+            Err(e) => {
+                eprintln!("{}", e);
+                return EXIT_FAILURE;
+            }
+        }
+    } else {
+        let catalog = match load_catalog() {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                eprintln!("Failed to load song catalog: {}", e);
+                return EXIT_FAILURE;
+            }
+        };
+        match get_best_song(&catalog).await {
+            Some(pick) => pick.explanation,
+            None => return EXIT_FAILURE,
+        }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&explanation) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("Failed to serialize explanation: {}", e);
+                return EXIT_FAILURE;
+            }
+        }
+    } else {
+        explanation.print();
+    }
+    EXIT_OK
+}
+
+fn load_last_explanation() -> Result<Explanation, String> {
+    let path = &get_config().paths.explanation_log;
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read explanation log '{}': {}", path.display(), e))?;
+    let line = text
+        .lines()
+        .rfind(|l| !l.trim().is_empty())
+        .ok_or(format!("Explanation log '{}' is empty", path.display()))?;
+    serde_json::from_str(line)
+        .map_err(|e| format!("Failed to parse explanation log '{}': {}", path.display(), e))
+}
+
+pub fn history(limit: usize) -> i32 {
+    let path = &get_config().paths.selection_log;
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read selection log '{}': {}", path.display(), e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    for line in &lines[lines.len().saturating_sub(limit)..] {
+        println!("{}", line);
+    }
+    EXIT_OK
+}
+
+pub async fn factors() -> i32 {
+    let factors = get_external_factors().await;
+    print_factors(&factors);
+    println!("Combined mood: {:?}", map_factors_to_mood(&factors));
+    EXIT_OK
+}
+
+pub fn library() -> i32 {
+    let catalog = match load_catalog() {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            eprintln!("Failed to load song catalog: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    for track in &catalog.tracks {
+        let number = track.track_number.map_or("-".to_string(), |n| n.to_string());
+        let duration = track.duration.map_or("?:??".to_string(), |d| {
+            format!("{}:{:02}", d.as_secs() / 60, d.as_secs() % 60)
+        });
+        println!(
+            "{} | {:>3} | {:<28} | {:>5} | {}",
+            track.album, number, track.title, duration, track.relative_path
+        );
+    }
+    catalog.report();
+
+    exit_code(catalog.unmatched_files.is_empty() && catalog.unmatched_ratings.is_empty())
+}
@@ -0,0 +1,5 @@
+mod args;
+mod commands;
+
+pub use args::{Cli, Command, translate_legacy_args};
+pub use commands::{EXIT_CONFIG, run};
@@ -0,0 +1,1133 @@
+use crate::external_factors::FactorRegistry;
+use crate::external_factors::get_holidays::load_holiday_file;
+use crate::external_factors::get_occupancy::normalize_device;
+use crate::external_factors::get_quarter::load_terms;
+use crate::external_factors::weather::weather_source;
+use crate::external_factors::mood::MoodScores;
+use chrono::Weekday;
+use dotenv::dotenv;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use toml::value::Datetime;
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+// What older versions had fixed, kept for deployments configured only through .env
+const LEGACY_WEATHER_OFFICE: &str = "SGX";
+const LEGACY_TIMEZONE: &str = "America/Los_Angeles";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub paths: PathsConfig,
+    pub location: LocationConfig,
+    pub schedule: ScheduleConfig,
+    pub providers: ProvidersConfig,
+    pub factors: BTreeMap<String, FactorSettings>,
+    pub cache: CacheConfig,
+    pub calendar: CalendarConfig,
+    pub quarter: QuarterConfig,
+    // Absolute path of the file this was loaded from, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    pub song_json: Option<PathBuf>,
+    pub album: Option<PathBuf>,
+    pub mpg123: PathBuf,
+    pub silence: Option<PathBuf>,
+    pub xdg_runtime_dir: Option<PathBuf>,
+    pub recent_songs: PathBuf,
+    pub selection_log: PathBuf,
+    pub explanation_log: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocationConfig {
+    // IANA name used for scheduling, the time factors and log timestamps, defaults to the system's
+    pub timezone: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    // weather.gov forecast office and grid, looked up from latitude/longitude when not set
+    pub weather_office: Option<String>,
+    pub grid_x: Option<u16>,
+    pub grid_y: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    // Named profiles, e.g. [schedule.profiles.weekday]
+    pub profiles: BTreeMap<String, ScheduleProfile>,
+    // Which profile each day of the week samples from
+    pub days: WeekProfiles,
+    // Every play lands inside this range and outside all quiet hours
+    pub allowed_hours: HourRange,
+    pub quiet_hours: Vec<HourRange>,
+    // Shortest time between two plays, raised to the longest track in the library if that is longer
+    pub min_gap_minutes: u32,
+    pub keep_alive_minutes: u32,
+    pub backend: SchedulerBackend,
+    // Where the systemd backend writes its user units, defaults to ~/.config/systemd/user
+    pub systemd_unit_dir: Option<PathBuf>,
+}
+
+// A day's plays: how many, and a Gaussian mixture over the hour of day they happen at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleProfile {
+    pub plays: PlayCount,
+    pub components: Vec<MixtureComponent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MixtureComponent {
+    pub mean: f64,
+    pub stddev: f64,
+    pub weight: f64,
+}
+
+// Either `plays = 12` or `plays = { min = 10, max = 14 }` for a uniformly random count
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PlayCount {
+    Fixed(u32),
+    Range(PlayRange),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+// Hours of the day as decimals, e.g. 22.5 is 22:30. A range whose end is before its
+// start wraps past midnight, so { start = 23.0, end = 7.0 } covers the night.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HourRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeekProfiles {
+    pub mon: String,
+    pub tue: String,
+    pub wed: String,
+    pub thu: String,
+    pub fri: String,
+    pub sat: String,
+    pub sun: String,
+    // Used instead on holidays and other days off from the calendar
+    pub holiday: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerBackend {
+    Cron,
+    Systemd,
+}
+
+// Per-factor switches, keyed by provider name e.g. [factors.market]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FactorSettings {
+    pub enabled: bool,
+    pub weight: f32,
+    // Overrides the provider's own cache TTL, 0 disables caching
+    pub cache_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub dir: PathBuf,
+    // How old a last known good value may be when its source is down
+    pub max_stale_hours: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    pub us_federal_holidays: bool,
+    // Extra holidays and special days, as an .ics or .toml file
+    pub file: Option<PathBuf>,
+}
+
+// Academic or fiscal terms, for the quarter factor and play counts by phase of the term
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuarterConfig {
+    pub terms: Vec<TermConfig>,
+    // Weeks 1 up to this one are the start of a term
+    pub start_weeks: u32,
+    // Week numbers of a term that are midterms
+    pub midterm_weeks: Vec<u32>,
+    // Last days of a term that are finals, for terms without a finals_start
+    pub finals_days: u32,
+    pub start_mood: MoodScores,
+    pub regular_mood: MoodScores,
+    pub midterms_mood: MoodScores,
+    pub finals_mood: MoodScores,
+    pub break_mood: MoodScores,
+    // Multiplies the day's number of plays in each phase
+    pub plays_scale: PhaseScales,
+}
+
+// Dates are inclusive and written bare, e.g. `start = 2026-09-24`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TermConfig {
+    pub name: String,
+    pub start: Datetime,
+    pub end: Datetime,
+    pub finals_start: Option<Datetime>,
+    #[serde(default)]
+    pub breaks: Vec<TermBreak>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TermBreak {
+    pub name: String,
+    pub start: Datetime,
+    pub end: Datetime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhaseScales {
+    pub start: f64,
+    pub regular: f64,
+    pub midterms: f64,
+    pub finals: f64,
+    // Breaks within a term and the days between terms
+    #[serde(rename = "break")]
+    pub on_break: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    pub weather: WeatherProviderConfig,
+    pub alerts: AlertsProviderConfig,
+    pub market: MarketProviderConfig,
+    pub mercury: MercuryProviderConfig,
+    pub moon: MoonProviderConfig,
+    pub occupancy: OccupancyProviderConfig,
+    pub season: SeasonProviderConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherProviderConfig {
+    pub backend: WeatherBackend,
+    pub user_agent: String,
+    // API roots, e.g. for a caching proxy
+    pub weather_gov_url: String,
+    pub open_meteo_url: String,
+    // Weather as JSON, for the file backend
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherBackend {
+    // api.weather.gov, US only
+    #[default]
+    WeatherGov,
+    OpenMeteo,
+    File,
+}
+
+// Active weather.gov alerts, for the location's point or a forecast zone. Uses the
+// weather provider's weather_gov_url and user_agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsProviderConfig {
+    // Zone or county code like "CAZ043", instead of latitude/longitude
+    pub zone: Option<String>,
+    // Event names as weather.gov writes them, e.g. "Evacuation Immediate". Playback is
+    // skipped while one of these is active.
+    pub suppress: Vec<String>,
+    // Events that play at soften_volume instead
+    pub soften: Vec<String>,
+    // Fraction of full volume, 0..1
+    pub soften_volume: f32,
+    pub heat_mood: MoodScores,
+    // Freeze, wind chill and winter storms
+    pub cold_mood: MoodScores,
+    pub wind_mood: MoodScores,
+    // Fire and red flag
+    pub fire_mood: MoodScores,
+    pub flood_mood: MoodScores,
+    // Thunderstorms, tornadoes and tropical storms
+    pub storm_mood: MoodScores,
+    // Air quality, smoke and dust
+    pub air_quality_mood: MoodScores,
+    pub other_mood: MoodScores,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketProviderConfig {
+    pub user_agent: String,
+    pub stock_symbol: String,
+    pub crypto_symbol: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MercuryProviderConfig {
+    pub url: String,
+    // Contribution while Mercury is retrograde
+    pub mood: MoodScores,
+}
+
+// Contribution of each part of the lunar cycle, only applied at night
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MoonProviderConfig {
+    pub new_mood: MoodScores,
+    pub waxing_mood: MoodScores,
+    pub full_mood: MoodScores,
+    pub waning_mood: MoodScores,
+}
+
+// Who is home, from the devices of each person on the local network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OccupancyProviderConfig {
+    pub people: Vec<PersonConfig>,
+    pub sources: Vec<PresenceSource>,
+    // Devices or names that are present, one per line, for the file source
+    pub presence_file: Option<PathBuf>,
+    pub ping_timeout_secs: u64,
+    // Plays nothing while every configured person is away
+    pub skip_when_empty: bool,
+    pub alone_mood: MoodScores,
+    // Two or more people home
+    pub company_mood: MoodScores,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PersonConfig {
+    pub name: String,
+    // MAC or IP addresses, e.g. "aa:bb:cc:dd:ee:ff" or "192.168.1.20"
+    pub devices: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceSource {
+    // The kernel's ARP/neighbour table, from `ip neigh show`
+    Neighbours,
+    // One ping to every configured IP address
+    Ping,
+    File,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeasonProviderConfig {
+    pub model: SeasonModel,
+}
+
+// Astronomical seasons turn at the solstices and equinoxes, meteorological ones on the
+// first of March, June, September and December
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeasonModel {
+    #[default]
+    Astronomical,
+    Meteorological,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        PathsConfig {
+            song_json: None,
+            album: None,
+            mpg123: PathBuf::from("mpg123"),
+            silence: None,
+            xdg_runtime_dir: None,
+            recent_songs: PathBuf::from("recent_songs.txt"),
+            selection_log: PathBuf::from("song_selections.txt"),
+            explanation_log: PathBuf::from("selection_explanations.jsonl"),
+        }
+    }
+}
+
+impl Default for LocationConfig {
+    fn default() -> Self {
+        LocationConfig {
+            timezone: iana_time_zone::get_timezone().unwrap_or("UTC".to_string()),
+            latitude: None,
+            longitude: None,
+            weather_office: None,
+            grid_x: None,
+            grid_y: None,
+        }
+    }
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        let profile = |plays, components: [(f64, f64); 2]| ScheduleProfile {
+            plays: PlayCount::Fixed(plays),
+            components: components
+                .iter()
+                .map(|&(mean, stddev)| MixtureComponent {
+                    mean,
+                    stddev,
+                    weight: 1.0,
+                })
+                .collect(),
+        };
+
+        ScheduleConfig {
+            profiles: BTreeMap::from([
+                ("weekday".to_string(), profile(12, [(11.5, 2.0), (18.5, 2.0)])),
+                ("weekend".to_string(), profile(12, [(12.5, 2.6), (20.0, 2.7)])),
+            ]),
+            days: WeekProfiles::default(),
+            allowed_hours: HourRange {
+                start: 0.0,
+                end: 24.0,
+            },
+            quiet_hours: vec![HourRange {
+                start: 23.0,
+                end: 7.0,
+            }],
+            min_gap_minutes: 10,
+            keep_alive_minutes: 5,
+            backend: SchedulerBackend::Cron,
+            systemd_unit_dir: None,
+        }
+    }
+}
+
+impl Default for WeekProfiles {
+    fn default() -> Self {
+        let weekday = || "weekday".to_string();
+        let weekend = || "weekend".to_string();
+        WeekProfiles {
+            mon: weekday(),
+            tue: weekday(),
+            wed: weekday(),
+            thu: weekday(),
+            fri: weekday(),
+            sat: weekend(),
+            sun: weekend(),
+            holiday: weekend(),
+        }
+    }
+}
+
+impl WeekProfiles {
+    pub fn profile_name(&self, day: Weekday) -> &str {
+        match day {
+            Weekday::Mon => &self.mon,
+            Weekday::Tue => &self.tue,
+            Weekday::Wed => &self.wed,
+            Weekday::Thu => &self.thu,
+            Weekday::Fri => &self.fri,
+            Weekday::Sat => &self.sat,
+            Weekday::Sun => &self.sun,
+        }
+    }
+}
+
+impl PlayCount {
+    // Rounded to whole plays, for the quarter's plays_scale
+    pub fn scaled(self, scale: f64) -> PlayCount {
+        let scale = |n: u32| (n as f64 * scale).round() as u32;
+        match self {
+            PlayCount::Fixed(n) => PlayCount::Fixed(scale(n)),
+            PlayCount::Range(range) => PlayCount::Range(PlayRange {
+                min: scale(range.min),
+                max: scale(range.max),
+            }),
+        }
+    }
+}
+
+impl HourRange {
+    pub fn contains(&self, hour: f64) -> bool {
+        if self.start <= self.end {
+            self.start <= hour && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+impl ScheduleConfig {
+    // Whether a play may happen at this hour of the day
+    pub fn is_allowed(&self, hour: f64) -> bool {
+        (0.0..24.0).contains(&hour)
+            && self.allowed_hours.contains(hour)
+            && !self.quiet_hours.iter().any(|q| q.contains(hour))
+    }
+}
+
+impl Default for FactorSettings {
+    fn default() -> Self {
+        FactorSettings {
+            enabled: true,
+            weight: 1.0,
+            cache_ttl_secs: None,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            dir: PathBuf::from("cache"),
+            max_stale_hours: 24,
+        }
+    }
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            us_federal_holidays: true,
+            file: None,
+        }
+    }
+}
+
+impl Default for QuarterConfig {
+    fn default() -> Self {
+        QuarterConfig {
+            terms: Vec::new(),
+            start_weeks: 2,
+            midterm_weeks: vec![5, 6],
+            finals_days: 7,
+            start_mood: MoodScores {
+                hopeful: 0.6,
+                happy: 0.4,
+                ..Default::default()
+            },
+            regular_mood: MoodScores {
+                relaxing: 0.6,
+                nostalgic: 0.4,
+                ..Default::default()
+            },
+            midterms_mood: MoodScores {
+                relaxing: 0.6,
+                melancholic: 0.4,
+                ..Default::default()
+            },
+            finals_mood: MoodScores {
+                relaxing: 0.6,
+                hopeful: 0.4,
+                ..Default::default()
+            },
+            break_mood: MoodScores {
+                happy: 0.7,
+                relaxing: 0.3,
+                ..Default::default()
+            },
+            plays_scale: PhaseScales::default(),
+        }
+    }
+}
+
+impl Default for PhaseScales {
+    fn default() -> Self {
+        PhaseScales {
+            start: 1.0,
+            regular: 1.0,
+            midterms: 1.0,
+            finals: 1.0,
+            on_break: 1.0,
+        }
+    }
+}
+
+impl PhaseScales {
+    fn iter(&self) -> [(&'static str, f64); 5] {
+        [
+            ("start", self.start),
+            ("regular", self.regular),
+            ("midterms", self.midterms),
+            ("finals", self.finals),
+            ("break", self.on_break),
+        ]
+    }
+}
+
+impl Default for WeatherProviderConfig {
+    fn default() -> Self {
+        WeatherProviderConfig {
+            backend: WeatherBackend::WeatherGov,
+            user_agent: "weather-app".to_string(),
+            weather_gov_url: "https://api.weather.gov".to_string(),
+            open_meteo_url: "https://api.open-meteo.com".to_string(),
+            file: None,
+        }
+    }
+}
+
+impl Default for AlertsProviderConfig {
+    fn default() -> Self {
+        let events = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        AlertsProviderConfig {
+            zone: None,
+            suppress: events(&[
+                "Evacuation Immediate",
+                "Shelter In Place Warning",
+                "Civil Danger Warning",
+                "Civil Emergency Message",
+                "Tornado Warning",
+                "Extreme Wind Warning",
+                "Fire Warning",
+                "Flash Flood Emergency",
+                "Hurricane Warning",
+                "Tsunami Warning",
+            ]),
+            soften: events(&[
+                "Severe Thunderstorm Warning",
+                "Flash Flood Warning",
+                "Red Flag Warning",
+                "Extreme Heat Warning",
+                "Excessive Heat Warning",
+                "High Wind Warning",
+                "Winter Storm Warning",
+                "Blizzard Warning",
+                "Air Quality Alert",
+                "Dense Smoke Advisory",
+            ]),
+            soften_volume: 0.5,
+            heat_mood: MoodScores {
+                relaxing: 0.6,
+                melancholic: 0.4,
+                ..Default::default()
+            },
+            cold_mood: MoodScores {
+                nostalgic: 0.6,
+                melancholic: 0.4,
+                ..Default::default()
+            },
+            wind_mood: MoodScores {
+                mysterious: 0.6,
+                nostalgic: 0.4,
+                ..Default::default()
+            },
+            fire_mood: MoodScores {
+                melancholic: 0.6,
+                mysterious: 0.4,
+                ..Default::default()
+            },
+            flood_mood: MoodScores {
+                melancholic: 0.7,
+                relaxing: 0.3,
+                ..Default::default()
+            },
+            storm_mood: MoodScores {
+                mysterious: 0.7,
+                melancholic: 0.3,
+                ..Default::default()
+            },
+            air_quality_mood: MoodScores {
+                melancholic: 0.5,
+                relaxing: 0.5,
+                ..Default::default()
+            },
+            other_mood: MoodScores {
+                mysterious: 0.5,
+                melancholic: 0.5,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Default for MarketProviderConfig {
+    fn default() -> Self {
+        MarketProviderConfig {
+            user_agent: "raspberry-pi".to_string(),
+            stock_symbol: "SPY".to_string(),
+            crypto_symbol: "BTC-USD".to_string(),
+        }
+    }
+}
+
+impl Default for MercuryProviderConfig {
+    fn default() -> Self {
+        MercuryProviderConfig {
+            url: "https://mercuryretrogradeapi.com".to_string(),
+            mood: MoodScores {
+                mysterious: 0.7,
+                melancholic: 0.3,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Default for MoonProviderConfig {
+    fn default() -> Self {
+        MoonProviderConfig {
+            new_mood: MoodScores {
+                melancholic: 0.7,
+                mysterious: 0.3,
+                ..Default::default()
+            },
+            waxing_mood: MoodScores {
+                hopeful: 0.6,
+                mysterious: 0.4,
+                ..Default::default()
+            },
+            full_mood: MoodScores {
+                mysterious: 0.8,
+                happy: 0.2,
+                ..Default::default()
+            },
+            waning_mood: MoodScores {
+                nostalgic: 0.6,
+                relaxing: 0.4,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Default for OccupancyProviderConfig {
+    fn default() -> Self {
+        OccupancyProviderConfig {
+            people: Vec::new(),
+            sources: vec![PresenceSource::Neighbours],
+            presence_file: None,
+            ping_timeout_secs: 1,
+            skip_when_empty: true,
+            alone_mood: MoodScores {
+                relaxing: 0.6,
+                nostalgic: 0.4,
+                ..Default::default()
+            },
+            company_mood: MoodScores {
+                happy: 0.7,
+                hopeful: 0.3,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Config {
+    // Every problem found, an empty list means the config is usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        match &self.paths.song_json {
+            None => problems.push("paths.song_json is not set (or SONG_JSON_PATH)".to_string()),
+            Some(p) if !p.is_file() => problems.push(format!(
+                "paths.song_json '{}' is not a readable file",
+                p.display()
+            )),
+            _ => {}
+        }
+        match &self.paths.album {
+            None => problems.push("paths.album is not set (or ALBUM_PATH)".to_string()),
+            Some(p) if !p.is_dir() => problems.push(format!(
+                "paths.album '{}' is not a directory",
+                p.display()
+            )),
+            _ => {}
+        }
+        if !is_executable(&self.paths.mpg123) {
+            problems.push(format!(
+                "paths.mpg123 '{}' was not found or is not executable",
+                self.paths.mpg123.display()
+            ));
+        }
+        if let Some(p) = &self.paths.silence
+            && !p.is_file()
+        {
+            problems.push(format!(
+                "paths.silence '{}' is not a readable file",
+                p.display()
+            ));
+        }
+
+        if self.location.timezone.parse::<chrono_tz::Tz>().is_err() {
+            problems.push(format!(
+                "location.timezone '{}' is not a known IANA timezone",
+                self.location.timezone
+            ));
+        }
+        if let Some(lat) = self.location.latitude
+            && !(-90.0..=90.0).contains(&lat)
+        {
+            problems.push(format!("location.latitude {} is outside -90..90", lat));
+        }
+        if let Some(lon) = self.location.longitude
+            && !(-180.0..=180.0).contains(&lon)
+        {
+            problems.push(format!("location.longitude {} is outside -180..180", lon));
+        }
+        let weather_enabled = self.factors.get("weather").is_none_or(|f| f.enabled);
+        let location = &self.location;
+        let has_grid = location.grid_x.is_some() || location.grid_y.is_some();
+        let full_grid = location.weather_office.is_some()
+            && location.grid_x.is_some()
+            && location.grid_y.is_some();
+        if has_grid && !full_grid {
+            problems.push(
+                "location.weather_office, grid_x and grid_y must be set together \
+                 (or WEATHER_OFFICE, GRID1, GRID2)"
+                    .to_string(),
+            );
+        }
+        if location.latitude.is_some() != location.longitude.is_some() {
+            problems.push("location.latitude and longitude must be set together".to_string());
+        }
+        if weather_enabled && let Err(e) = weather_source(self) {
+            problems.push(e);
+        }
+        let alerts = &self.providers.alerts;
+        if !(0.0..=1.0).contains(&alerts.soften_volume) {
+            problems.push(format!(
+                "providers.alerts.soften_volume {} is outside 0..1",
+                alerts.soften_volume
+            ));
+        }
+        if alerts.zone.as_ref().is_some_and(|zone| zone.trim().is_empty()) {
+            problems.push("providers.alerts.zone is empty".to_string());
+        }
+
+        let allowed_minutes = (0..24 * 60)
+            .filter(|minute| self.schedule.is_allowed(*minute as f64 / 60.0))
+            .count() as u32;
+        // Busiest phase of the term, when terms are configured and the factor is on
+        let quarter_enabled = self.factors.get("quarter").is_none_or(|f| f.enabled);
+        let max_scale = if self.quarter.terms.is_empty() || !quarter_enabled {
+            1.0
+        } else {
+            self.quarter.plays_scale.iter().into_iter().map(|(_, s)| s).fold(0.0, f64::max)
+        };
+        for (name, profile) in &self.schedule.profiles {
+            problems.extend(validate_profile(name, profile));
+
+            let max_plays = match profile.plays.scaled(max_scale) {
+                PlayCount::Fixed(n) => n,
+                PlayCount::Range(range) => range.max,
+            };
+            let needed = max_plays.saturating_sub(1) * self.schedule.min_gap_minutes;
+            if allowed_minutes > 0 && needed >= allowed_minutes {
+                problems.push(format!(
+                    "schedule.profiles.{} can't fit {} plays {} minutes apart in {} allowed minutes",
+                    name, max_plays, self.schedule.min_gap_minutes, allowed_minutes
+                ));
+            }
+        }
+        let days = &self.schedule.days;
+        let day_profiles = WEEK
+            .iter()
+            .map(|day| (day.to_string().to_lowercase(), days.profile_name(*day)))
+            .chain([("holiday".to_string(), days.holiday.as_str())]);
+        for (day, name) in day_profiles {
+            if !self.schedule.profiles.contains_key(name) {
+                problems.push(format!(
+                    "schedule.days.{} uses profile '{}', which is not in schedule.profiles",
+                    day, name
+                ));
+            }
+        }
+        problems.extend(validate_hour_range("schedule.allowed_hours", &self.schedule.allowed_hours));
+        for (i, quiet) in self.schedule.quiet_hours.iter().enumerate() {
+            problems.extend(validate_hour_range(&format!("schedule.quiet_hours[{}]", i), quiet));
+        }
+        if allowed_minutes == 0 {
+            problems.push(
+                "schedule.allowed_hours and schedule.quiet_hours leave no time to play".to_string(),
+            );
+        }
+        if self.schedule.keep_alive_minutes == 0 || self.schedule.keep_alive_minutes > 59 {
+            problems.push("schedule.keep_alive_minutes must be within 1..59".to_string());
+        }
+
+        if let Some(file) = &self.calendar.file
+            && let Err(e) = load_holiday_file(file)
+        {
+            problems.push(format!("calendar.file '{}' could not be read: {}", file.display(), e));
+        }
+
+        if let Err(e) = load_terms(&self.quarter) {
+            problems.push(format!("quarter.terms: {}", e));
+        }
+        if self.quarter.start_weeks == 0 {
+            problems.push("quarter.start_weeks must be at least 1".to_string());
+        }
+        if self.quarter.midterm_weeks.contains(&0) {
+            problems.push("quarter.midterm_weeks are numbered from 1".to_string());
+        }
+        if self.quarter.finals_days == 0 {
+            problems.push("quarter.finals_days must be at least 1".to_string());
+        }
+        for (phase, scale) in self.quarter.plays_scale.iter() {
+            if !scale.is_finite() || scale < 0.0 {
+                problems.push(format!("quarter.plays_scale.{} must be zero or positive", phase));
+            }
+        }
+
+        let occupancy = &self.providers.occupancy;
+        for person in &occupancy.people {
+            if person.devices.is_empty() {
+                problems.push(format!("providers.occupancy person '{}' has no devices", person.name));
+            }
+            for device in &person.devices {
+                if normalize_device(device).is_none() {
+                    problems.push(format!(
+                        "providers.occupancy device '{}' of '{}' is not a MAC or IP address",
+                        device, person.name
+                    ));
+                }
+            }
+        }
+        if occupancy.sources.contains(&PresenceSource::File) && occupancy.presence_file.is_none() {
+            problems.push("providers.occupancy.presence_file is needed by the file source".to_string());
+        }
+        if occupancy.ping_timeout_secs == 0 {
+            problems.push("providers.occupancy.ping_timeout_secs must be at least 1".to_string());
+        }
+
+        let known_factors = FactorRegistry::builtin(self).names();
+        for (name, settings) in &self.factors {
+            if !known_factors.contains(&name.as_str()) {
+                problems.push(format!(
+                    "factors.{} is not a known factor (expected one of: {})",
+                    name,
+                    known_factors.join(", ")
+                ));
+            }
+            if !settings.weight.is_finite() || settings.weight < 0.0 {
+                problems.push(format!("factors.{}.weight must be zero or positive", name));
+            }
+        }
+
+        problems
+    }
+}
+
+const WEEK: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+fn validate_hour_range(name: &str, range: &HourRange) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (field, hour) in [("start", range.start), ("end", range.end)] {
+        if !hour.is_finite() || !(0.0..=24.0).contains(&hour) {
+            problems.push(format!("{}.{} must be within 0..24 hours", name, field));
+        }
+    }
+    if range.start == range.end {
+        problems.push(format!("{} is empty, start and end are both {}", name, range.start));
+    }
+    problems
+}
+
+fn validate_profile(name: &str, profile: &ScheduleProfile) -> Vec<String> {
+    let mut problems = Vec::new();
+    let prefix = format!("schedule.profiles.{}", name);
+
+    if let PlayCount::Range(range) = profile.plays
+        && range.min > range.max
+    {
+        problems.push(format!("{}.plays min {} is above max {}", prefix, range.min, range.max));
+    }
+
+    if profile.components.is_empty() {
+        problems.push(format!("{}.components must have at least one component", prefix));
+    }
+    for (i, c) in profile.components.iter().enumerate() {
+        if !c.mean.is_finite() || !(0.0..24.0).contains(&c.mean) {
+            problems.push(format!("{}.components[{}].mean must be within 0..24 hours", prefix, i));
+        }
+        if !c.stddev.is_finite() || c.stddev <= 0.0 {
+            problems.push(format!("{}.components[{}].stddev must be positive", prefix, i));
+        }
+        if !c.weight.is_finite() || c.weight < 0.0 {
+            problems.push(format!("{}.components[{}].weight must be zero or positive", prefix, i));
+        }
+    }
+    let total_weight: f64 = profile.components.iter().map(|c| c.weight).sum();
+    if !profile.components.is_empty() && total_weight.is_finite() && total_weight <= 0.0 {
+        problems.push(format!("{}.components weights must not all be zero", prefix));
+    }
+
+    problems
+}
+
+fn is_executable(path: &Path) -> bool {
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    // Bare command name, look it up on PATH like the shell would
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(path).is_file()))
+        .unwrap_or(false)
+}
+
+// Reads the TOML file (if present) then applies the legacy environment variables on top
+pub fn load_config(path: &Path) -> Result<Config, String> {
+    dotenv().ok();
+
+    let mut config = if path.exists() {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config '{}': {}", path.display(), e))?;
+        let mut config = toml::from_str::<Config>(&text)
+            .map_err(|e| format!("Failed to parse config '{}': {}", path.display(), e))?;
+        config.source = Some(path.canonicalize().unwrap_or(path.to_path_buf()));
+        config
+    } else {
+        let mut config = Config::default();
+        config.location.timezone = LEGACY_TIMEZONE.to_string();
+        config
+    };
+
+    apply_env_overrides(&mut config)?;
+    Ok(config)
+}
+
+fn apply_env_overrides(config: &mut Config) -> Result<(), String> {
+    if let Ok(v) = env::var("SONG_JSON_PATH") {
+        config.paths.song_json = Some(PathBuf::from(v));
+    }
+    if let Ok(v) = env::var("ALBUM_PATH") {
+        config.paths.album = Some(PathBuf::from(v));
+    }
+    if let Ok(v) = env::var("MPG123_PATH") {
+        config.paths.mpg123 = PathBuf::from(v);
+    }
+    if let Ok(v) = env::var("SILENCE_PATH") {
+        config.paths.silence = Some(PathBuf::from(v));
+    }
+    if let Ok(v) = env::var("XDG_RUNTIME_DIR") {
+        config.paths.xdg_runtime_dir = Some(PathBuf::from(v));
+    }
+    if let Ok(v) = env::var("WEATHER_OFFICE") {
+        config.location.weather_office = Some(v.trim().to_string());
+    }
+    if let Ok(v) = env::var("GRID1") {
+        config.location.grid_x = Some(
+            v.trim()
+                .parse()
+                .map_err(|_| format!("GRID1 '{}' is not a valid grid coordinate", v))?,
+        );
+    }
+    if let Ok(v) = env::var("GRID2") {
+        config.location.grid_y = Some(
+            v.trim()
+                .parse()
+                .map_err(|_| format!("GRID2 '{}' is not a valid grid coordinate", v))?,
+        );
+    }
+    let legacy_grid = env::var_os("GRID1").is_some() || env::var_os("GRID2").is_some();
+    if legacy_grid && config.location.weather_office.is_none() {
+        eprintln!(
+            "Warning: GRID1/GRID2 without WEATHER_OFFICE is deprecated, assuming {}. \
+             Set WEATHER_OFFICE or location.weather_office.",
+            LEGACY_WEATHER_OFFICE
+        );
+        config.location.weather_office = Some(LEGACY_WEATHER_OFFICE.to_string());
+    }
+    Ok(())
+}
+
+// Loads, validates and installs the config for the rest of the process
+pub fn init_config(path: &Path) -> Result<&'static Config, String> {
+    let config = load_config(path)?;
+    let problems = config.validate();
+    if !problems.is_empty() {
+        return Err(format!(
+            "Invalid config '{}':\n  - {}",
+            path.display(),
+            problems.join("\n  - ")
+        ));
+    }
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+pub fn get_config() -> &'static Config {
+    CONFIG.get().expect("config must be initialised at startup")
+}
+
+// Prints the resolved config and every problem found, returns whether it is valid
+pub fn check_config(path: &Path) -> bool {
+    let config = match load_config(path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+
+    if path.exists() {
+        println!("# Loaded from {}", path.display());
+    } else {
+        println!("# {} not found, using defaults and environment", path.display());
+    }
+    match toml::to_string_pretty(&config) {
+        Ok(text) => println!("{}", text),
+        Err(e) => eprintln!("Failed to print config: {}", e),
+    }
+
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("Config OK");
+        true
+    } else {
+        for problem in &problems {
+            eprintln!("Problem: {}", problem);
+        }
+        eprintln!("{} problem(s) found", problems.len());
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_counts_scale_to_whole_plays() {
+        let cases = [
+            (PlayCount::Fixed(12), 0.5, PlayCount::Fixed(6)),
+            (PlayCount::Fixed(5), 0.5, PlayCount::Fixed(3)),
+            (PlayCount::Fixed(12), 1.25, PlayCount::Fixed(15)),
+            (PlayCount::Fixed(3), 0.1, PlayCount::Fixed(0)),
+            (
+                PlayCount::Range(PlayRange { min: 3, max: 9 }),
+                0.75,
+                PlayCount::Range(PlayRange { min: 2, max: 7 }),
+            ),
+        ];
+        for (plays, scale, expected) in cases {
+            assert_eq!(plays.scaled(scale), expected, "{:?} x {}", plays, scale);
+        }
+    }
+}
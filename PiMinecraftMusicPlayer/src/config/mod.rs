@@ -0,0 +1,9 @@
+mod load_config;
+
+pub use load_config::{
+    AlertsProviderConfig, CalendarConfig, Config, FactorSettings, MixtureComponent, PersonConfig,
+    PlayCount, PresenceSource, QuarterConfig, ScheduleConfig, ScheduleProfile, SchedulerBackend,
+    SeasonModel, WeatherBackend, check_config, get_config, init_config,
+};
+#[cfg(test)]
+pub use load_config::HourRange;
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub fetched_at: u64,
+    // Hash of the settings the value was fetched with, 0 for entries from before it was kept
+    #[serde(default)]
+    pub config_hash: u64,
+    pub value: Value,
+}
+
+// One JSON file per factor source, kept between runs so scheduled plays
+// minutes apart don't hit the same APIs again
+pub struct FactorCache {
+    dir: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now_secs().saturating_sub(self.fetched_at))
+    }
+}
+
+impl FactorCache {
+    pub fn new(dir: PathBuf) -> Self {
+        FactorCache { dir }
+    }
+
+    fn entry_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    // `config` is whatever settings the value depends on, an entry fetched with other
+    // settings is a miss rather than a reading for the wrong place or symbol
+    pub fn get(&self, name: &str, config: &Value) -> Option<CacheEntry> {
+        let text = fs::read_to_string(self.entry_path(name)).ok()?;
+        let entry: CacheEntry = match serde_json::from_str(&text) {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Ignoring corrupt cache entry for {}: {}", name, e);
+                return None;
+            }
+        };
+        if entry.config_hash != config_hash(config) {
+            eprintln!("Ignoring cache entry for {}, its settings have changed", name);
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub fn put(&self, name: &str, config: &Value, value: &Value) {
+        let entry = CacheEntry {
+            fetched_at: now_secs(),
+            config_hash: config_hash(config),
+            value: value.clone(),
+        };
+
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            eprintln!("Failed to create cache dir '{}': {}", self.dir.display(), e);
+            return;
+        }
+
+        // Write then rename so a crash mid-write never leaves a half entry behind
+        let path = self.entry_path(name);
+        let tmp_path = path.with_extension("json.tmp");
+        let result = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(&tmp_path, text).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&tmp_path, &path).map_err(|e| e.to_string()));
+
+        if let Err(e) = result {
+            eprintln!("Failed to write cache entry for {}: {}", name, e);
+        }
+    }
+}
+
+// FNV-1a over the JSON text, which unlike std's hasher stays the same between builds.
+// serde_json sorts object keys, so equal settings always give the same text.
+fn config_hash(config: &Value) -> u64 {
+    config.to_string().bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_from_other_settings_are_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FactorCache::new(dir.path().to_path_buf());
+        let spy = Value::from("SPY");
+        cache.put("market", &spy, &Value::from(1.5));
+
+        assert_eq!(cache.get("market", &spy).unwrap().value, Value::from(1.5));
+        assert!(cache.get("market", &Value::from("QQQ")).is_none());
+        assert!(cache.get("weather", &spy).is_none());
+
+        // Written before the settings hash existed
+        fs::write(dir.path().join("old.json"), r#"{"fetched_at": 0, "value": 1}"#).unwrap();
+        assert!(cache.get("old", &Value::Null).is_none());
+    }
+}
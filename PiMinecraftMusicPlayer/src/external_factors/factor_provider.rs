@@ -0,0 +1,32 @@
+use super::mood::MoodScores;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+// A source of one external factor. `fetch` returns the raw reading as JSON so the
+// registry can log it without knowing its shape, `mood` turns it into a contribution.
+#[async_trait]
+pub trait FactorProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Providers can switch themselves off, e.g. when they are missing required settings
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    // How long a fetched reading stays good in the on-disk cache, None disables caching
+    fn cache_ttl(&self) -> Option<Duration> {
+        None
+    }
+
+    // The settings a cached reading depends on, so changing them refetches
+    fn cache_config(&self) -> Value {
+        Value::Null
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>>;
+
+    // None means the reading has no effect on the mood
+    fn mood(&self, value: &Value) -> Option<MoodScores>;
+}
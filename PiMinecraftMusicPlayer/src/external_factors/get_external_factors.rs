@@ -1,22 +1,217 @@
 use super::{
-    get_market_data, get_mercury_retrograde, get_time,
-    get_weather_data::{self, get_weather_data},
+    factor_cache::FactorCache,
+    factor_provider::FactorProvider,
+    get_holidays::HolidayProvider,
+    get_market_data::MarketProvider,
+    get_mercury_retrograde::MercuryRetrogradeProvider,
+    get_moon::MoonProvider,
+    get_occupancy::OccupancyProvider,
+    get_quarter::QuarterProvider,
+    get_season::SeasonProvider,
+    get_sun::SunProvider,
+    get_time::TimeProvider,
+    weather::{AlertsProvider, WeatherProvider},
+    mood::MoodScores,
 };
+use crate::config::{Config, FactorSettings, get_config};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum FactorStatus {
+    Fresh,
+    Cached { age: Duration },
+    Stale { age: Duration, error: String },
+    Missing { error: String },
+}
+
+pub struct FactorReading {
+    pub name: &'static str,
+    pub status: FactorStatus,
+    pub value: Option<Value>,
+    pub mood: Option<MoodScores>,
+    pub weight: f32,
+}
 
 pub struct ExternalFactors {
-    pub weather: get_weather_data::Weather,
-    pub time: get_time::Time,
-    pub market: get_market_data::Market,
-    pub mercury_retrograde: bool,
+    pub readings: Vec<FactorReading>,
 }
 
-pub async fn get_external_factors() -> ExternalFactors {
-    ExternalFactors {
-        weather: get_weather_data().await.unwrap(),
-        time: get_time::get_pacific_time(),
-        market: get_market_data::get_market_data().await.unwrap(),
-        mercury_retrograde: get_mercury_retrograde::get_mercury_retrograde()
-            .await
-            .is_ok(),
+impl fmt::Display for FactorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactorStatus::Fresh => write!(f, "{}", self.label()),
+            FactorStatus::Cached { age } => write!(f, "{}, age {}s", self.label(), age.as_secs()),
+            FactorStatus::Stale { age, error } => {
+                write!(f, "{}, age {}s ({})", self.label(), age.as_secs(), error)
+            }
+            FactorStatus::Missing { error } => write!(f, "{} ({})", self.label(), error),
+        }
+    }
+}
+
+impl FactorStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FactorStatus::Fresh => "fresh",
+            FactorStatus::Cached { .. } => "cached",
+            FactorStatus::Stale { .. } => "stale",
+            FactorStatus::Missing { .. } => "missing",
+        }
+    }
+}
+
+impl ExternalFactors {
+    // Names of the factors that could not be read fresh, for the selection log
+    pub fn degraded(&self) -> Vec<String> {
+        self.readings
+            .iter()
+            .filter(|r| matches!(r.status, FactorStatus::Stale { .. } | FactorStatus::Missing { .. }))
+            .map(|r| format!("{} ({})", r.name, r.status.label()))
+            .collect()
+    }
+}
+
+pub struct FactorRegistry {
+    providers: Vec<(Box<dyn FactorProvider>, FactorSettings)>,
+    cache: Option<FactorCache>,
+    max_stale: Duration,
+}
+
+impl FactorRegistry {
+    pub fn new(cache: Option<FactorCache>, max_stale: Duration) -> Self {
+        FactorRegistry {
+            providers: Vec::new(),
+            cache,
+            max_stale,
+        }
+    }
+
+    // All built-in providers, with enabled/weight taken from the [factors] table
+    pub fn builtin(config: &Config) -> Self {
+        let cache = FactorCache::new(config.cache.dir.clone());
+        let max_stale = Duration::from_secs(config.cache.max_stale_hours * 3600);
+
+        let mut registry = FactorRegistry::new(Some(cache), max_stale);
+        registry.register(config, Box::new(WeatherProvider));
+        registry.register(config, Box::new(AlertsProvider));
+        registry.register(config, Box::new(TimeProvider));
+        registry.register(config, Box::new(SeasonProvider));
+        registry.register(config, Box::new(SunProvider));
+        registry.register(config, Box::new(HolidayProvider));
+        registry.register(config, Box::new(QuarterProvider));
+        registry.register(config, Box::new(OccupancyProvider));
+        registry.register(config, Box::new(MarketProvider));
+        registry.register(config, Box::new(MercuryRetrogradeProvider));
+        registry.register(config, Box::new(MoonProvider));
+        registry
     }
+
+    pub fn register(&mut self, config: &Config, provider: Box<dyn FactorProvider>) {
+        let settings = config
+            .factors
+            .get(provider.name())
+            .cloned()
+            .unwrap_or_default();
+        self.providers.push((provider, settings));
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|(p, _)| p.name()).collect()
+    }
+
+    // One factor read the same way collect() reads it, through the cache, for deciding
+    // whether to play at all. None when the factor is disabled, unreadable or only has a
+    // stale value, so an old reading never silences the player.
+    pub async fn read_current(&self, name: &str) -> Option<Value> {
+        let (provider, settings) = self.providers.iter().find(|(p, _)| p.name() == name)?;
+        if !settings.enabled || !provider.enabled() {
+            return None;
+        }
+        match self.read(provider.as_ref(), settings).await {
+            (FactorStatus::Fresh | FactorStatus::Cached { .. }, value) => value,
+            _ => None,
+        }
+    }
+
+    pub async fn collect(&self) -> ExternalFactors {
+        let mut readings = Vec::new();
+
+        for (provider, settings) in &self.providers {
+            if !settings.enabled || !provider.enabled() {
+                eprintln!("Factor {}: disabled", provider.name());
+                continue;
+            }
+
+            let (status, value) = self.read(provider.as_ref(), settings).await;
+            let mood = value.as_ref().and_then(|v| provider.mood(v));
+
+            readings.push(FactorReading {
+                name: provider.name(),
+                status,
+                value,
+                mood,
+                weight: settings.weight,
+            });
+        }
+
+        ExternalFactors { readings }
+    }
+
+    // Cache hit if young enough, otherwise fetch and fall back to the last known good value
+    async fn read(
+        &self,
+        provider: &dyn FactorProvider,
+        settings: &FactorSettings,
+    ) -> (FactorStatus, Option<Value>) {
+        let name = provider.name();
+        let ttl = match settings.cache_ttl_secs {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => provider.cache_ttl(),
+        };
+        let cache = self.cache.as_ref().filter(|_| ttl.is_some());
+        let config = provider.cache_config();
+        let cached = cache.and_then(|c| c.get(name, &config));
+
+        if let (Some(entry), Some(ttl)) = (&cached, ttl)
+            && entry.age() < ttl
+        {
+            eprintln!("Factor {}: cache hit, age {}s", name, entry.age().as_secs());
+            return (FactorStatus::Cached { age: entry.age() }, Some(entry.value.clone()));
+        }
+
+        match provider.fetch().await {
+            Ok(value) => {
+                if let Some(cache) = cache {
+                    cache.put(name, &config, &value);
+                }
+                (FactorStatus::Fresh, Some(value))
+            }
+            Err(e) => match cached.filter(|entry| entry.age() < self.max_stale) {
+                Some(entry) => {
+                    eprintln!(
+                        "Factor {} unavailable, using cached value from {}s ago: {}",
+                        name,
+                        entry.age().as_secs(),
+                        e
+                    );
+                    let status = FactorStatus::Stale {
+                        age: entry.age(),
+                        error: e.to_string(),
+                    };
+                    (status, Some(entry.value))
+                }
+                None => {
+                    eprintln!("Factor {} unavailable: {}", name, e);
+                    (FactorStatus::Missing { error: e.to_string() }, None)
+                }
+            },
+        }
+    }
+}
+
+pub async fn get_external_factors() -> ExternalFactors {
+    FactorRegistry::builtin(get_config()).collect().await
 }
@@ -0,0 +1,411 @@
+use super::factor_provider::FactorProvider;
+use super::get_season::{Season, current_season};
+use super::get_time;
+use super::mood::{MoodScores, normalize};
+use crate::config::{CalendarConfig, get_config};
+use async_trait::async_trait;
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use ical::IcalParser;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use toml::value::Datetime;
+
+static CALENDAR: OnceCell<Calendar> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holiday {
+    pub name: String,
+    pub date: NaiveDate,
+    // Days off are scheduled with the holiday profile, other special days only affect the mood
+    pub day_off: bool,
+    pub mood: Option<MoodScores>,
+}
+
+pub struct Calendar {
+    us_federal_holidays: bool,
+    holidays: Vec<Holiday>,
+}
+
+// One [[holiday]] entry of a TOML calendar file. `end` is inclusive, for days off spanning several dates.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HolidayEntry {
+    name: String,
+    date: Datetime,
+    end: Option<Datetime>,
+    #[serde(default = "default_day_off")]
+    day_off: bool,
+    mood: Option<MoodScores>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HolidayFile {
+    #[serde(default)]
+    holiday: Vec<HolidayEntry>,
+}
+
+fn default_day_off() -> bool {
+    true
+}
+
+impl Calendar {
+    pub fn load(config: &CalendarConfig) -> Result<Self, Box<dyn Error>> {
+        let holidays = match &config.file {
+            Some(file) => load_holiday_file(file)?,
+            None => Vec::new(),
+        };
+        Ok(Calendar {
+            us_federal_holidays: config.us_federal_holidays,
+            holidays,
+        })
+    }
+
+    // An entry from the user's file names the day and sets its mood over a built-in holiday
+    // on the same date, but can't take away the federal day off
+    pub fn holiday_on(&self, date: NaiveDate) -> Option<Holiday> {
+        let user = self.holidays.iter().find(|h| h.date == date).cloned();
+        let federal = if self.us_federal_holidays {
+            // New Year's Day on a Saturday is observed on the last day of the previous year
+            [date.year(), date.year() + 1]
+                .into_iter()
+                .flat_map(us_federal_holidays)
+                .find(|h| h.date == date)
+        } else {
+            None
+        };
+
+        match (user, federal) {
+            (Some(user), Some(federal)) => Some(Holiday {
+                day_off: user.day_off || federal.day_off,
+                mood: user.mood.or(federal.mood),
+                ..user
+            }),
+            (user, federal) => user.or(federal),
+        }
+    }
+}
+
+// Loaded once per run. A broken calendar file is reported and the built-in holidays are kept.
+pub fn get_calendar() -> &'static Calendar {
+    CALENDAR.get_or_init(|| {
+        let config = &get_config().calendar;
+        Calendar::load(config).unwrap_or_else(|e| {
+            eprintln!("Failed to load the holiday calendar, using built-in holidays only: {}", e);
+            Calendar {
+                us_federal_holidays: config.us_federal_holidays,
+                holidays: Vec::new(),
+            }
+        })
+    })
+}
+
+// Reads an .ics file, or a TOML file of [[holiday]] entries for anything else
+pub fn load_holiday_file(path: &Path) -> Result<Vec<Holiday>, Box<dyn Error>> {
+    let is_ics = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ics"));
+    if is_ics {
+        return load_ics(path);
+    }
+
+    let file: HolidayFile = toml::from_str(&fs::read_to_string(path)?)?;
+    let mut holidays = Vec::new();
+    for entry in file.holiday {
+        let start = toml_date(&entry.date)?;
+        let end = entry.end.as_ref().map_or(Ok(start), toml_date)?;
+        if end < start {
+            return Err(format!("holiday '{}' ends before it starts", entry.name).into());
+        }
+        for date in start.iter_days().take_while(|d| *d <= end) {
+            holidays.push(Holiday {
+                name: entry.name.clone(),
+                date,
+                day_off: entry.day_off,
+                mood: entry.mood.clone(),
+            });
+        }
+    }
+    Ok(holidays)
+}
+
+// TOML dates are written bare, e.g. `date = 2026-12-24`
+pub fn toml_date(value: &Datetime) -> Result<NaiveDate, Box<dyn Error>> {
+    value
+        .date
+        .and_then(|d| NaiveDate::from_ymd_opt(d.year as i32, d.month as u32, d.day as u32))
+        .ok_or_else(|| format!("'{}' is not a date", value).into())
+}
+
+// Every event is a day off. All-day events cover DTSTART up to the day before DTEND,
+// timed events only their start date. Recurrence rules are not expanded.
+fn load_ics(path: &Path) -> Result<Vec<Holiday>, Box<dyn Error>> {
+    let mut holidays = Vec::new();
+    for calendar in IcalParser::new(BufReader::new(File::open(path)?)) {
+        for event in calendar?.events {
+            let property = |name: &str| {
+                event
+                    .properties
+                    .iter()
+                    .find(|p| p.name == name)
+                    .and_then(|p| p.value.clone())
+            };
+
+            let start = property("DTSTART").ok_or("event without DTSTART")?;
+            let start = parse_ics_date(&start)?;
+            let end = match property("DTEND") {
+                Some(end) if end.len() == 8 => parse_ics_date(&end)?.pred_opt().unwrap_or(start),
+                _ => start,
+            };
+            let name = property("SUMMARY").unwrap_or_else(|| "Holiday".to_string());
+
+            for date in start.iter_days().take_while(|d| *d <= end) {
+                holidays.push(Holiday {
+                    name: name.clone(),
+                    date,
+                    day_off: true,
+                    mood: None,
+                });
+            }
+        }
+    }
+    Ok(holidays)
+}
+
+// DATE (20261224) or DATE-TIME (20261224T090000Z) values, only the date is kept
+fn parse_ics_date(value: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    let date = value.get(..8).ok_or_else(|| format!("invalid date '{}'", value))?;
+    Ok(NaiveDate::parse_from_str(date, "%Y%m%d")?)
+}
+
+// The days off for federal holidays that year, moved to the Friday or Monday when they fall on a weekend
+pub fn us_federal_holidays(year: i32) -> Vec<Holiday> {
+    let fixed = |month, day| NaiveDate::from_ymd_opt(year, month, day).map(observed);
+    let nth = |month, weekday, n| NaiveDate::from_weekday_of_month_opt(year, month, weekday, n);
+    let last_monday_of_may = nth(5, Weekday::Mon, 5).or(nth(5, Weekday::Mon, 4));
+
+    let nostalgic = MoodScores {
+        nostalgic: 0.7,
+        happy: 0.3,
+        ..Default::default()
+    };
+    let happy = MoodScores {
+        happy: 0.7,
+        hopeful: 0.3,
+        ..Default::default()
+    };
+    let hopeful = MoodScores {
+        hopeful: 0.7,
+        nostalgic: 0.3,
+        ..Default::default()
+    };
+    let remembrance = MoodScores {
+        melancholic: 0.5,
+        nostalgic: 0.5,
+        ..Default::default()
+    };
+    let relaxing = MoodScores {
+        relaxing: 0.7,
+        happy: 0.3,
+        ..Default::default()
+    };
+
+    let mut days = vec![
+        ("New Year's Day", fixed(1, 1), &nostalgic),
+        ("Martin Luther King Jr. Day", nth(1, Weekday::Mon, 3), &hopeful),
+        ("Presidents' Day", nth(2, Weekday::Mon, 3), &relaxing),
+        ("Memorial Day", last_monday_of_may, &remembrance),
+        ("Independence Day", fixed(7, 4), &happy),
+        ("Labor Day", nth(9, Weekday::Mon, 1), &relaxing),
+        ("Columbus Day", nth(10, Weekday::Mon, 2), &relaxing),
+        ("Veterans Day", fixed(11, 11), &remembrance),
+        ("Thanksgiving Day", nth(11, Weekday::Thu, 4), &nostalgic),
+        ("Christmas Day", fixed(12, 25), &nostalgic),
+    ];
+    if year >= 2021 {
+        days.push(("Juneteenth", fixed(6, 19), &hopeful));
+    }
+
+    days.into_iter()
+        .filter_map(|(name, date, mood)| {
+            Some(Holiday {
+                name: name.to_string(),
+                date: date?,
+                day_off: true,
+                mood: Some(mood.clone()),
+            })
+        })
+        .collect()
+}
+
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date.checked_sub_days(Days::new(1)).unwrap_or(date),
+        Weekday::Sun => date.checked_add_days(Days::new(1)).unwrap_or(date),
+        _ => date,
+    }
+}
+
+// For holidays from the user's file without a mood of their own: winter holidays lean nostalgic
+fn default_mood(season: Season) -> MoodScores {
+    match season {
+        Season::Winter => MoodScores {
+            nostalgic: 0.7,
+            happy: 0.3,
+            ..Default::default()
+        },
+        _ => MoodScores {
+            happy: 0.6,
+            relaxing: 0.4,
+            ..Default::default()
+        },
+    }
+}
+
+pub struct HolidayProvider;
+
+#[async_trait]
+impl FactorProvider for HolidayProvider {
+    fn name(&self) -> &'static str {
+        "holiday"
+    }
+
+    // Null on ordinary days
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        let today = get_time::get_local_dt().date_naive();
+        Ok(serde_json::to_value(get_calendar().holiday_on(today))?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let holiday: Holiday = serde_json::from_value(value.clone()).ok()?;
+        let mood = match holiday.mood {
+            Some(mood) => mood,
+            None => {
+                // Midday, so the astronomical model gives the season most of the day is in
+                let noon = holiday.date.and_time(NaiveTime::from_hms_opt(12, 0, 0)?);
+                default_mood(current_season(&Utc.from_utc_datetime(&noon)))
+            }
+        };
+        Some(normalize(mood))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn federal(name: &str, year: i32) -> Option<NaiveDate> {
+        us_federal_holidays(year).into_iter().find(|h| h.name == name).map(|h| h.date)
+    }
+
+    fn calendar(holidays: Vec<Holiday>) -> Calendar {
+        Calendar {
+            us_federal_holidays: true,
+            holidays,
+        }
+    }
+
+    #[test]
+    fn federal_holiday_rules() {
+        let cases = [
+            // Nth weekday of the month
+            ("Martin Luther King Jr. Day", 2026, date(2026, 1, 19)),
+            ("Labor Day", 2026, date(2026, 9, 7)),
+            ("Columbus Day", 2026, date(2026, 10, 12)),
+            ("Thanksgiving Day", 2026, date(2026, 11, 26)),
+            // Last Monday of May, in a May with four Mondays and one with five
+            ("Memorial Day", 2024, date(2024, 5, 27)),
+            ("Memorial Day", 2021, date(2021, 5, 31)),
+            // Saturdays move to the Friday, Sundays to the Monday
+            ("Independence Day", 2026, date(2026, 7, 3)),
+            ("Independence Day", 2021, date(2021, 7, 5)),
+            ("Christmas Day", 2027, date(2027, 12, 24)),
+            ("Veterans Day", 2026, date(2026, 11, 11)),
+            ("New Year's Day", 2022, date(2021, 12, 31)),
+            ("Juneteenth", 2026, date(2026, 6, 19)),
+        ];
+        for (name, year, expected) in cases {
+            assert_eq!(federal(name, year), Some(expected), "{} {}", name, year);
+        }
+        assert_eq!(federal("Juneteenth", 2020), None);
+    }
+
+    #[test]
+    fn saturday_new_years_day_is_observed_the_year_before() {
+        let calendar = calendar(Vec::new());
+        let holiday = calendar.holiday_on(date(2021, 12, 31)).unwrap();
+        assert_eq!(holiday.name, "New Year's Day");
+        assert!(holiday.day_off);
+        assert!(calendar.holiday_on(date(2022, 1, 1)).is_none());
+    }
+
+    #[test]
+    fn user_entries_keep_the_federal_day_off() {
+        let calendar = calendar(vec![
+            Holiday {
+                name: "Fireworks".to_string(),
+                date: date(2026, 7, 3),
+                day_off: false,
+                mood: None,
+            },
+            Holiday {
+                name: "Team offsite".to_string(),
+                date: date(2026, 7, 8),
+                day_off: false,
+                mood: None,
+            },
+        ]);
+
+        let holiday = calendar.holiday_on(date(2026, 7, 3)).unwrap();
+        assert_eq!(holiday.name, "Fireworks");
+        assert!(holiday.day_off);
+        assert!(holiday.mood.is_some());
+        assert!(!calendar.holiday_on(date(2026, 7, 8)).unwrap().day_off);
+    }
+
+    #[test]
+    fn ics_end_dates_are_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("holidays.ics");
+        fs::write(
+            &path,
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+             BEGIN:VEVENT\r\nSUMMARY:Winter break\r\n\
+             DTSTART;VALUE=DATE:20261224\r\nDTEND;VALUE=DATE:20261226\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nSUMMARY:Dentist\r\n\
+             DTSTART:20261230T090000Z\r\nDTEND:20261231T100000Z\r\nEND:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+
+        let days: Vec<(String, NaiveDate)> = load_holiday_file(&path)
+            .unwrap()
+            .into_iter()
+            .map(|h| (h.name, h.date))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("Winter break".to_string(), date(2026, 12, 24)),
+                ("Winter break".to_string(), date(2026, 12, 25)),
+                // Timed events only cover their start date
+                ("Dentist".to_string(), date(2026, 12, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_mood_follows_the_season() {
+        assert!(default_mood(Season::Winter).nostalgic > 0.0);
+        assert_eq!(default_mood(Season::Summer).nostalgic, 0.0);
+    }
+}
@@ -1,34 +1,43 @@
-use reqwest::{Client, header::USER_AGENT};
+use super::factor_provider::FactorProvider;
+use super::http;
+use super::mood::{MoodScores, normalize, sigmoid};
+use crate::config::get_config;
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Market {
     pub spy: f32,
     pub btc: f32,
 }
 
-pub async fn get_market_data() -> Result<Market, Box<dyn std::error::Error>> {
-    let spy_url = "https://query1.finance.yahoo.com/v8/finance/chart/SPY?region=US&lang=en-US&includePrePost=false&interval=2m&useYfid=true&range=1d%60";
-    let btc_url = "https://query1.finance.yahoo.com/v8/finance/chart/BTC-USD?region=US&lang=en-US&includePrePost=false&interval=2m&useYfid=true&range=1d%60";
+pub async fn get_market_data() -> Result<Market, Box<dyn Error>> {
+    let config = &get_config().providers.market;
+    let spy_url = chart_url(&config.stock_symbol);
+    let btc_url = chart_url(&config.crypto_symbol);
 
-    let client = Client::new();
+    let client = http::client()?;
 
     let res = client
         .get(spy_url)
-        .header(USER_AGENT, "raspberry-pi")
+        .header(USER_AGENT, &config.user_agent)
         .send()
         .await?;
 
-    let body = res.text().await.unwrap();
+    let body = res.text().await?;
     let v_spy: Value = serde_json::from_str(&body)?;
 
     let res = client
         .get(btc_url)
-        .header(USER_AGENT, "raspberry-pi")
+        .header(USER_AGENT, &config.user_agent)
         .send()
         .await?;
 
-    let body = res.text().await.unwrap();
+    let body = res.text().await?;
     let v_btc: Value = serde_json::from_str(&body)?;
 
     let v_spy_cur_price = v_spy["chart"]["result"][0]["meta"]["regularMarketPrice"]
@@ -57,3 +66,44 @@ pub async fn get_market_data() -> Result<Market, Box<dyn std::error::Error>> {
 
     Ok(market)
 }
+
+fn chart_url(symbol: &str) -> String {
+    format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}?region=US&lang=en-US&includePrePost=false&interval=2m&useYfid=true&range=1d%60",
+        symbol
+    )
+}
+
+pub struct MarketProvider;
+
+#[async_trait]
+impl FactorProvider for MarketProvider {
+    fn name(&self) -> &'static str {
+        "market"
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5 * 60))
+    }
+
+    fn cache_config(&self) -> Value {
+        serde_json::to_value(&get_config().providers.market).unwrap_or_default()
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(get_market_data().await?)?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let market: Market = serde_json::from_value(value.clone()).ok()?;
+
+        let market_mood = MoodScores {
+            hopeful: sigmoid(market.spy),
+            melancholic: 1.0 - sigmoid(market.spy),
+            mysterious: sigmoid(-market.btc),
+            happy: sigmoid(market.btc),
+            ..Default::default()
+        };
+        Some(normalize(market_mood))
+    }
+}
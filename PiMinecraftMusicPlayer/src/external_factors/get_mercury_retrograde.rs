@@ -1,14 +1,51 @@
-
+use super::factor_provider::FactorProvider;
+use super::http;
+use super::mood::MoodScores;
+use crate::config::get_config;
+use async_trait::async_trait;
 use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
 
-pub async fn get_mercury_retrograde() -> Result<bool, Box<dyn std::error::Error>> {
-    let body = reqwest::get("https://mercuryretrogradeapi.com")
+pub async fn get_mercury_retrograde() -> Result<bool, Box<dyn Error>> {
+    let body = http::client()?
+        .get(&get_config().providers.mercury.url)
+        .send()
         .await?
         .text()
         .await?;
 
     let v: Value = serde_json::from_str(&body)?;
-    let v = &v["is_retrograde"].as_bool().unwrap();
+    let v = v["is_retrograde"].as_bool().ok_or("Missing is_retrograde")?;
+
+    Ok(v)
+}
+
+pub struct MercuryRetrogradeProvider;
+
+#[async_trait]
+impl FactorProvider for MercuryRetrogradeProvider {
+    fn name(&self) -> &'static str {
+        "mercury_retrograde"
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(24 * 60 * 60))
+    }
+
+    fn cache_config(&self) -> Value {
+        Value::from(get_config().providers.mercury.url.as_str())
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(Value::Bool(get_mercury_retrograde().await?))
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        if !value.as_bool()? {
+            return None;
+        }
 
-    Ok(*v)
+        Some(get_config().providers.mercury.mood.clone())
+    }
 }
@@ -0,0 +1,163 @@
+use super::factor_provider::FactorProvider;
+use super::get_sun::get_sun;
+use super::get_time;
+use super::mood::{MoodScores, normalize};
+use crate::config::get_config;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::f64::consts::PI;
+
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+// Mean new moon of 2000-01-06 14:20 UTC as a Julian date, the true one was at 18:14
+const REFERENCE_NEW_MOON_JD: f64 = 2451550.09766;
+// Named phases (new, first quarter, ...) last about a day either side of the exact moment
+const PHASE_HALF_WIDTH: f64 = 1.0 / SYNODIC_MONTH_DAYS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoonPhase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Moon {
+    pub age_days: f64,
+    // Lit fraction of the disc, 0 at new moon and 1 at full moon
+    pub illumination: f64,
+    pub phase: MoonPhase,
+    pub is_night: bool,
+}
+
+// Mean lunar cycle from a known new moon, within about half a day of the true phase
+pub fn moon_at<Tz: TimeZone>(at: &DateTime<Tz>) -> (f64, f64, MoonPhase) {
+    let jd = at.timestamp() as f64 / 86400.0 + 2440587.5;
+    let cycle = ((jd - REFERENCE_NEW_MOON_JD) / SYNODIC_MONTH_DAYS).rem_euclid(1.0);
+    let illumination = (1.0 - (2.0 * PI * cycle).cos()) / 2.0;
+
+    let near = |target: f64| (cycle - target).abs() < PHASE_HALF_WIDTH;
+    let phase = if near(0.0) || near(1.0) {
+        MoonPhase::New
+    } else if near(0.25) {
+        MoonPhase::FirstQuarter
+    } else if near(0.5) {
+        MoonPhase::Full
+    } else if near(0.75) {
+        MoonPhase::LastQuarter
+    } else if cycle < 0.25 {
+        MoonPhase::WaxingCrescent
+    } else if cycle < 0.5 {
+        MoonPhase::WaxingGibbous
+    } else if cycle < 0.75 {
+        MoonPhase::WaningGibbous
+    } else {
+        MoonPhase::WaningCrescent
+    };
+
+    (cycle * SYNODIC_MONTH_DAYS, illumination, phase)
+}
+
+pub fn get_moon() -> Moon {
+    let now = get_time::get_local_dt();
+    let (age_days, illumination, phase) = moon_at(&now);
+    // The sun when coordinates are set, otherwise 18:00 to 06:00
+    let is_night = get_sun().map_or(now.hour() >= 18 || now.hour() < 6, |sun| !sun.is_daytime);
+    Moon {
+        age_days,
+        illumination,
+        phase,
+        is_night,
+    }
+}
+
+pub struct MoonProvider;
+
+#[async_trait]
+impl FactorProvider for MoonProvider {
+    fn name(&self) -> &'static str {
+        "moon"
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(get_moon())?)
+    }
+
+    // Like mercury_retrograde, only has an effect some of the time: here, at night
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let moon: Moon = serde_json::from_value(value.clone()).ok()?;
+        if !moon.is_night {
+            return None;
+        }
+
+        let config = &get_config().providers.moon;
+        let moon_mood = match moon.phase {
+            MoonPhase::New => &config.new_mood,
+            MoonPhase::WaxingCrescent | MoonPhase::FirstQuarter | MoonPhase::WaxingGibbous => {
+                &config.waxing_mood
+            }
+            MoonPhase::Full => &config.full_mood,
+            MoonPhase::WaningGibbous | MoonPhase::LastQuarter | MoonPhase::WaningCrescent => {
+                &config.waning_mood
+            }
+        };
+        Some(normalize(moon_mood.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn known_new_and_full_moons() {
+        // The reference new moon, the 2024 eclipse new moon and recent full moons
+        for at in [utc(2000, 1, 6, 18, 14), utc(2024, 4, 8, 18, 21)] {
+            let (age_days, illumination, phase) = moon_at(&at);
+            assert_eq!(phase, MoonPhase::New, "{}", at);
+            assert!(illumination < 0.01, "{}: {}", at, illumination);
+            assert!(!(0.6..=SYNODIC_MONTH_DAYS - 0.6).contains(&age_days), "{}: {}", at, age_days);
+        }
+        for at in [utc(2024, 10, 17, 11, 26), utc(2025, 12, 4, 23, 14)] {
+            let (age_days, illumination, phase) = moon_at(&at);
+            assert_eq!(phase, MoonPhase::Full, "{}", at);
+            assert!(illumination > 0.99, "{}: {}", at, illumination);
+            assert!((age_days - SYNODIC_MONTH_DAYS / 2.0).abs() < 0.6, "{}: {}", at, age_days);
+        }
+    }
+
+    #[test]
+    fn named_phases_last_a_day_either_side() {
+        // The model's own reference, so the edges are exact
+        let secs = (REFERENCE_NEW_MOON_JD - 2440587.5) * 86400.0;
+        let new = Utc.timestamp_opt(secs.round() as i64, 0).unwrap();
+        let full = new + Duration::seconds((SYNODIC_MONTH_DAYS / 2.0 * 86400.0) as i64);
+        let (inside, outside) = (Duration::hours(23), Duration::hours(25));
+        let cases = [
+            (new - inside, MoonPhase::New),
+            (new + inside, MoonPhase::New),
+            (new - outside, MoonPhase::WaningCrescent),
+            (new + outside, MoonPhase::WaxingCrescent),
+            (full - inside, MoonPhase::Full),
+            (full + inside, MoonPhase::Full),
+            (full - outside, MoonPhase::WaxingGibbous),
+            (full + outside, MoonPhase::WaningGibbous),
+        ];
+        for (at, phase) in cases {
+            assert_eq!(moon_at(&at).2, phase, "{}", at);
+        }
+    }
+}
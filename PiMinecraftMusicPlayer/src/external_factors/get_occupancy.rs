@@ -0,0 +1,274 @@
+use super::factor_provider::FactorProvider;
+use super::mood::{MoodScores, normalize};
+use super::FactorRegistry;
+use crate::config::{PersonConfig, PresenceSource, get_config};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Occupancy {
+    // People with at least one device on the network
+    pub count: u32,
+    pub present: Vec<String>,
+    // Everyone configured, home or not
+    pub people: u32,
+}
+
+// Lowercase MAC with colons, or the IP address. None for anything else.
+pub fn normalize_device(device: &str) -> Option<String> {
+    let device = device.trim();
+    if let Ok(ip) = device.parse::<IpAddr>() {
+        return Some(ip.to_string());
+    }
+    let mac = device.to_ascii_lowercase().replace('-', ":");
+    let octets: Vec<&str> = mac.split(':').collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|o| o.len() == 2 && o.chars().all(|c| c.is_ascii_hexdigit()));
+    valid.then_some(mac)
+}
+
+// Addresses in `ip neigh show` output, skipping FAILED and INCOMPLETE entries
+// for devices that stopped answering
+fn parse_neighbours(output: &str) -> HashSet<String> {
+    let mut seen = HashSet::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(state) = fields.last() else {
+            continue;
+        };
+        if matches!(*state, "FAILED" | "INCOMPLETE") {
+            continue;
+        }
+        if let Some(ip) = fields.first().and_then(|ip| normalize_device(ip)) {
+            seen.insert(ip);
+        }
+        let mac = fields
+            .iter()
+            .position(|f| *f == "lladdr")
+            .and_then(|i| fields.get(i + 1))
+            .and_then(|mac| normalize_device(mac));
+        if let Some(mac) = mac {
+            seen.insert(mac);
+        }
+    }
+    seen
+}
+
+fn read_neighbours() -> Result<HashSet<String>, Box<dyn Error>> {
+    let output = Command::new("ip").args(["neigh", "show"]).output()?;
+    if !output.status.success() {
+        return Err(format!("ip neigh show exited with {}", output.status).into());
+    }
+    Ok(parse_neighbours(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// One device address or person name per line, `#` starts a comment
+fn read_presence_file(path: &Path) -> Result<HashSet<String>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(text
+        .lines()
+        .filter_map(|line| line.split('#').next()?.split_whitespace().next())
+        .map(|entry| normalize_device(entry).unwrap_or_else(|| entry.to_lowercase()))
+        .collect())
+}
+
+// Pings every address at once, returning the ones that answered
+fn ping_all(ips: &[String], timeout_secs: u64) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut children = Vec::new();
+    for ip in ips {
+        let child = Command::new("ping")
+            .args(["-c", "1", "-W", &timeout_secs.to_string(), ip])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        children.push((ip, child));
+    }
+
+    let mut answered = HashSet::new();
+    for (ip, mut child) in children {
+        if child.wait()?.success() {
+            answered.insert(ip.clone());
+        }
+    }
+    Ok(answered)
+}
+
+fn is_present(person: &PersonConfig, seen: &HashSet<String>) -> bool {
+    seen.contains(&person.name.to_lowercase())
+        || person
+            .devices
+            .iter()
+            .filter_map(|d| normalize_device(d))
+            .any(|d| seen.contains(&d))
+}
+
+// Reads every configured source, failing only when none of them could be read
+pub fn get_occupancy() -> Result<Occupancy, Box<dyn Error>> {
+    let config = &get_config().providers.occupancy;
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+
+    for source in &config.sources {
+        let result = match source {
+            PresenceSource::Neighbours => read_neighbours(),
+            PresenceSource::File => match &config.presence_file {
+                Some(path) => read_presence_file(path),
+                None => Err("presence_file is not set".into()),
+            },
+            // Once everything else has been read, so only people not seen yet are pinged
+            PresenceSource::Ping => continue,
+        };
+        match result {
+            Ok(found) => seen.extend(found),
+            Err(e) => errors.push(format!("{:?}: {}", source, e)),
+        }
+    }
+
+    if config.sources.contains(&PresenceSource::Ping) {
+        let ips: Vec<String> = config
+            .people
+            .iter()
+            .filter(|person| !is_present(person, &seen))
+            .flat_map(|person| person.devices.iter().filter_map(|d| normalize_device(d)))
+            .filter(|d| d.parse::<IpAddr>().is_ok())
+            .collect();
+        match ping_all(&ips, config.ping_timeout_secs) {
+            Ok(answered) => seen.extend(answered),
+            Err(e) => errors.push(format!("Ping: {}", e)),
+        }
+    }
+
+    if !errors.is_empty() && errors.len() == config.sources.len() {
+        return Err(errors.join(", ").into());
+    }
+    for error in &errors {
+        eprintln!("Presence source unavailable: {}", error);
+    }
+
+    let present: Vec<String> = config
+        .people
+        .iter()
+        .filter(|person| is_present(person, &seen))
+        .map(|person| person.name.clone())
+        .collect();
+    Ok(Occupancy {
+        count: present.len() as u32,
+        present,
+        people: config.people.len() as u32,
+    })
+}
+
+// Whether to skip playback, from the same cached reading the mood uses
+pub async fn nobody_home(registry: &FactorRegistry) -> bool {
+    if !get_config().providers.occupancy.skip_when_empty {
+        return false;
+    }
+    registry
+        .read_current(OccupancyProvider.name())
+        .await
+        .and_then(|value| serde_json::from_value::<Occupancy>(value).ok())
+        .is_some_and(|o| o.count == 0)
+}
+
+pub struct OccupancyProvider;
+
+#[async_trait]
+impl FactorProvider for OccupancyProvider {
+    fn name(&self) -> &'static str {
+        "occupancy"
+    }
+
+    fn enabled(&self) -> bool {
+        !get_config().providers.occupancy.people.is_empty()
+    }
+
+    // Long enough for the song pick to reuse the reading play_song checked
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(2 * 60))
+    }
+
+    fn cache_config(&self) -> Value {
+        let config = &get_config().providers.occupancy;
+        serde_json::to_value((&config.people, &config.sources, &config.presence_file))
+            .unwrap_or_default()
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(get_occupancy()?)?)
+    }
+
+    // Nobody to play for when the house is empty
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let occupancy: Occupancy = serde_json::from_value(value.clone()).ok()?;
+        let config = &get_config().providers.occupancy;
+        let occupancy_mood = match occupancy.count {
+            0 => return None,
+            1 => &config.alone_mood,
+            _ => &config.company_mood,
+        };
+        Some(normalize(occupancy_mood.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str, devices: &[&str]) -> PersonConfig {
+        PersonConfig {
+            name: name.to_string(),
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn devices_are_normalized() {
+        assert_eq!(normalize_device("AA-BB-CC-DD-EE-0F").as_deref(), Some("aa:bb:cc:dd:ee:0f"));
+        assert_eq!(normalize_device(" 192.168.1.20 ").as_deref(), Some("192.168.1.20"));
+        assert_eq!(normalize_device("fe80:0::1").as_deref(), Some("fe80::1"));
+        assert_eq!(normalize_device("aa:bb:cc:dd:ee"), None);
+        assert_eq!(normalize_device("phone"), None);
+    }
+
+    #[test]
+    fn neighbours_skip_failed_entries() {
+        let output = "\
+192.168.1.1 dev wlan0 lladdr 11:22:33:44:55:66 REACHABLE
+192.168.1.20 dev wlan0 lladdr AA:BB:CC:DD:EE:FF STALE
+192.168.1.21 dev wlan0 FAILED
+192.168.1.22 dev wlan0 lladdr aa:aa:aa:aa:aa:aa INCOMPLETE
+fe80::1 dev wlan0 lladdr 11:22:33:44:55:66 router REACHABLE
+";
+        let seen = parse_neighbours(output);
+        assert!(seen.contains("192.168.1.20"));
+        assert!(seen.contains("aa:bb:cc:dd:ee:ff"));
+        assert!(seen.contains("fe80::1"));
+        assert!(!seen.contains("192.168.1.21"));
+        assert!(!seen.contains("aa:aa:aa:aa:aa:aa"));
+    }
+
+    #[test]
+    fn presence_file_counts_people() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("presence.txt");
+        fs::write(&path, "# home right now\nAA-BB-CC-DD-EE-FF\n\nSam  # no phone today\n").unwrap();
+        let seen = read_presence_file(&path).unwrap();
+
+        let alex = person("Alex", &["aa:bb:cc:dd:ee:ff", "192.168.1.20"]);
+        let sam = person("Sam", &["192.168.1.30"]);
+        let robin = person("Robin", &["11:22:33:44:55:66"]);
+        assert!(is_present(&alex, &seen));
+        assert!(is_present(&sam, &seen));
+        assert!(!is_present(&robin, &seen));
+    }
+}
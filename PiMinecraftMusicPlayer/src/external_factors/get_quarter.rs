@@ -0,0 +1,347 @@
+use super::factor_provider::FactorProvider;
+use super::get_holidays::toml_date;
+use super::get_time;
+use super::mood::{MoodScores, normalize};
+use crate::config::{QuarterConfig, get_config};
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+static TERMS: OnceCell<Vec<Term>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarterPhase {
+    Start,
+    Regular,
+    Midterms,
+    Finals,
+    Break,
+}
+
+// Where a date falls in the configured terms. Term and week are None between terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarterWeek {
+    pub term: Option<String>,
+    // 1 for the week the term starts in
+    pub week: Option<u32>,
+    pub weeks_in_term: Option<u32>,
+    pub phase: QuarterPhase,
+    // Set during a break within a term
+    pub break_name: Option<String>,
+}
+
+pub struct Term {
+    name: String,
+    start: NaiveDate,
+    end: NaiveDate,
+    finals_start: NaiveDate,
+    breaks: Vec<(String, NaiveDate, NaiveDate)>,
+}
+
+impl fmt::Display for QuarterPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            QuarterPhase::Start => "start",
+            QuarterPhase::Regular => "regular",
+            QuarterPhase::Midterms => "midterms",
+            QuarterPhase::Finals => "finals",
+            QuarterPhase::Break => "break",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for QuarterWeek {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.term, self.week, &self.break_name) {
+            (Some(term), Some(week), Some(name)) => {
+                write!(f, "{} week {} ({})", term, week, name)
+            }
+            (Some(term), Some(week), None) => {
+                write!(f, "{} week {} ({})", term, week, self.phase)
+            }
+            _ => f.write_str("break between terms"),
+        }
+    }
+}
+
+// Terms sorted by start, checked for dates that are out of order or overlap
+pub fn load_terms(config: &QuarterConfig) -> Result<Vec<Term>, Box<dyn Error>> {
+    let mut terms = Vec::new();
+    for entry in &config.terms {
+        let start = toml_date(&entry.start)?;
+        let end = toml_date(&entry.end)?;
+        if end < start {
+            return Err(format!("term '{}' ends before it starts", entry.name).into());
+        }
+        let finals_start = match &entry.finals_start {
+            Some(date) => toml_date(date)?,
+            None => end
+                .checked_sub_days(Days::new(config.finals_days.saturating_sub(1) as u64))
+                .unwrap_or(start)
+                .max(start),
+        };
+        if finals_start < start || finals_start > end {
+            return Err(format!("finals of term '{}' start outside the term", entry.name).into());
+        }
+
+        let mut breaks = Vec::new();
+        for b in &entry.breaks {
+            let (break_start, break_end) = (toml_date(&b.start)?, toml_date(&b.end)?);
+            if break_end < break_start || break_start < start || break_end > end {
+                return Err(format!(
+                    "break '{}' of term '{}' must end after it starts and be within the term",
+                    b.name, entry.name
+                )
+                .into());
+            }
+            breaks.push((b.name.clone(), break_start, break_end));
+        }
+
+        terms.push(Term {
+            name: entry.name.clone(),
+            start,
+            end,
+            finals_start,
+            breaks,
+        });
+    }
+
+    terms.sort_by_key(|t| t.start);
+    for pair in terms.windows(2) {
+        if pair[1].start <= pair[0].end {
+            return Err(format!("terms '{}' and '{}' overlap", pair[0].name, pair[1].name).into());
+        }
+    }
+    Ok(terms)
+}
+
+// Loaded once per run, config validation has already rejected broken terms
+fn get_terms() -> &'static [Term] {
+    TERMS.get_or_init(|| {
+        load_terms(&get_config().quarter).unwrap_or_else(|e| {
+            eprintln!("Failed to load quarter.terms: {}", e);
+            Vec::new()
+        })
+    })
+}
+
+// None before the first term and after the last one
+fn week_in(terms: &[Term], config: &QuarterConfig, date: NaiveDate) -> Option<QuarterWeek> {
+    let Some(term) = terms.iter().find(|t| t.start <= date && date <= t.end) else {
+        let between = terms.iter().any(|t| t.end < date) && terms.iter().any(|t| t.start > date);
+        return between.then_some(QuarterWeek {
+            term: None,
+            week: None,
+            weeks_in_term: None,
+            phase: QuarterPhase::Break,
+            break_name: None,
+        });
+    };
+
+    let week = (date - term.start).num_days() as u32 / 7 + 1;
+    let break_name = term
+        .breaks
+        .iter()
+        .find(|(_, start, end)| *start <= date && date <= *end)
+        .map(|(name, _, _)| name.clone());
+    let phase = if break_name.is_some() {
+        QuarterPhase::Break
+    } else if date >= term.finals_start {
+        QuarterPhase::Finals
+    } else if config.midterm_weeks.contains(&week) {
+        QuarterPhase::Midterms
+    } else if week <= config.start_weeks {
+        QuarterPhase::Start
+    } else {
+        QuarterPhase::Regular
+    };
+
+    Some(QuarterWeek {
+        term: Some(term.name.clone()),
+        week: Some(week),
+        weeks_in_term: Some((term.end - term.start).num_days() as u32 / 7 + 1),
+        phase,
+        break_name,
+    })
+}
+
+pub fn quarter_week(date: NaiveDate) -> Option<QuarterWeek> {
+    week_in(get_terms(), &get_config().quarter, date)
+}
+
+// How much to scale the day's play count by, 1.0 outside the configured terms and while
+// [factors.quarter] is disabled
+pub fn plays_scale(date: NaiveDate) -> f64 {
+    let config = get_config();
+    if !config.factors.get(QuarterProvider.name()).is_none_or(|f| f.enabled) {
+        return 1.0;
+    }
+    let scales = &config.quarter.plays_scale;
+    match quarter_week(date).map(|w| w.phase) {
+        Some(QuarterPhase::Start) => scales.start,
+        Some(QuarterPhase::Regular) => scales.regular,
+        Some(QuarterPhase::Midterms) => scales.midterms,
+        Some(QuarterPhase::Finals) => scales.finals,
+        Some(QuarterPhase::Break) => scales.on_break,
+        None => 1.0,
+    }
+}
+
+pub struct QuarterProvider;
+
+#[async_trait]
+impl FactorProvider for QuarterProvider {
+    fn name(&self) -> &'static str {
+        "quarter"
+    }
+
+    fn enabled(&self) -> bool {
+        !get_config().quarter.terms.is_empty()
+    }
+
+    // Null outside the configured terms
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        let today = get_time::get_local_dt().date_naive();
+        Ok(serde_json::to_value(quarter_week(today))?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let week: QuarterWeek = serde_json::from_value(value.clone()).ok()?;
+        let config = &get_config().quarter;
+        let quarter_mood = match week.phase {
+            QuarterPhase::Start => &config.start_mood,
+            QuarterPhase::Regular => &config.regular_mood,
+            QuarterPhase::Midterms => &config.midterms_mood,
+            QuarterPhase::Finals => &config.finals_mood,
+            QuarterPhase::Break => &config.break_mood,
+        };
+        Some(normalize(quarter_mood.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TERMS: &str = r#"
+        [[terms]]
+        name = "Fall"
+        start = 2026-09-24
+        end = 2026-12-12
+        finals_start = 2026-12-05
+        breaks = [
+            { name = "Thanksgiving", start = 2026-11-26, end = 2026-11-29 },
+            { name = "Study days", start = 2026-12-05, end = 2026-12-06 },
+        ]
+
+        [[terms]]
+        name = "Winter"
+        start = 2027-01-04
+        end = 2027-03-20
+    "#;
+
+    fn config(text: &str) -> QuarterConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn phase(config: &QuarterConfig, date: NaiveDate) -> Option<QuarterPhase> {
+        week_in(&load_terms(config).unwrap(), config, date).map(|w| w.phase)
+    }
+
+    #[test]
+    fn broken_terms_are_rejected() {
+        let cases = [
+            // Winter starts the day Fall ends
+            (
+                r#"terms = [{ name = "Fall", start = 2026-09-24, end = 2026-12-12 },
+                            { name = "Winter", start = 2026-12-12, end = 2027-03-20 }]"#,
+                "overlap",
+            ),
+            (r#"terms = [{ name = "Fall", start = 2026-12-12, end = 2026-09-24 }]"#, "ends before"),
+            (
+                r#"terms = [{ name = "Fall", start = 2026-09-24, end = 2026-12-12,
+                             finals_start = 2026-12-20 }]"#,
+                "finals",
+            ),
+            (
+                r#"terms = [{ name = "Fall", start = 2026-09-24, end = 2026-12-12,
+                             breaks = [{ name = "Late", start = 2026-12-10, end = 2026-12-14 }] }]"#,
+                "within the term",
+            ),
+        ];
+        for (text, problem) in cases {
+            let e = load_terms(&config(text)).err().expect(text).to_string();
+            assert!(e.contains(problem), "{}: {}", text, e);
+        }
+    }
+
+    #[test]
+    fn finals_fall_back_to_the_last_days() {
+        let config = config(TERMS);
+        // finals_days = 7, so the last week of Winter
+        assert_eq!(phase(&config, date(2027, 3, 13)), Some(QuarterPhase::Regular));
+        assert_eq!(phase(&config, date(2027, 3, 14)), Some(QuarterPhase::Finals));
+        assert_eq!(phase(&config, date(2027, 3, 20)), Some(QuarterPhase::Finals));
+
+        let short = r#"
+            finals_days = 0
+            terms = [{ name = "Summer", start = 2027-06-28, end = 2027-07-02 }]
+        "#;
+        let terms = load_terms(&self::config(short)).unwrap();
+        assert_eq!(terms[0].finals_start, date(2027, 7, 2));
+        let long = r#"
+            finals_days = 30
+            terms = [{ name = "Summer", start = 2027-06-28, end = 2027-07-02 }]
+        "#;
+        let terms = load_terms(&self::config(long)).unwrap();
+        assert_eq!(terms[0].finals_start, date(2027, 6, 28));
+    }
+
+    #[test]
+    fn phases_within_a_term() {
+        let config = config(TERMS);
+        let cases = [
+            (date(2026, 9, 24), QuarterPhase::Start),
+            (date(2026, 10, 7), QuarterPhase::Start),
+            (date(2026, 10, 8), QuarterPhase::Regular),
+            // Weeks 5 and 6
+            (date(2026, 10, 22), QuarterPhase::Midterms),
+            (date(2026, 11, 4), QuarterPhase::Midterms),
+            (date(2026, 11, 5), QuarterPhase::Regular),
+            // A break wins over the week and over finals
+            (date(2026, 11, 26), QuarterPhase::Break),
+            (date(2026, 12, 5), QuarterPhase::Break),
+            (date(2026, 12, 7), QuarterPhase::Finals),
+        ];
+        for (date, expected) in cases {
+            assert_eq!(phase(&config, date), Some(expected), "{}", date);
+        }
+
+        let terms = load_terms(&config).unwrap();
+        let thanksgiving = week_in(&terms, &config, date(2026, 11, 27)).unwrap();
+        assert_eq!(thanksgiving.week, Some(10));
+        assert_eq!(thanksgiving.break_name.as_deref(), Some("Thanksgiving"));
+    }
+
+    #[test]
+    fn days_between_terms_are_a_break() {
+        let config = config(TERMS);
+        let terms = load_terms(&config).unwrap();
+        let winter_break = week_in(&terms, &config, date(2026, 12, 20)).unwrap();
+        assert_eq!(winter_break.phase, QuarterPhase::Break);
+        assert_eq!(winter_break.term, None);
+        // Before the first term and after the last one
+        assert!(week_in(&terms, &config, date(2026, 9, 1)).is_none());
+        assert!(week_in(&terms, &config, date(2027, 4, 1)).is_none());
+    }
+}
@@ -0,0 +1,298 @@
+use super::factor_provider::FactorProvider;
+use super::get_time;
+use super::mood::MoodScores;
+use crate::config::{SeasonModel, get_config};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Season {
+    Spring,
+    Summer,
+    Fall,
+    Winter,
+}
+
+impl Season {
+    fn opposite(self) -> Season {
+        match self {
+            Season::Spring => Season::Fall,
+            Season::Summer => Season::Winter,
+            Season::Fall => Season::Spring,
+            Season::Winter => Season::Summer,
+        }
+    }
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Season::Spring => "spring",
+            Season::Summer => "summer",
+            Season::Fall => "fall",
+            Season::Winter => "winter",
+        };
+        f.write_str(name)
+    }
+}
+
+// Season at the configured location, northern hemisphere when no latitude is set
+pub fn current_season<Tz: TimeZone>(at: &DateTime<Tz>) -> Season {
+    let config = get_config();
+    season_at(at, config.location.latitude.unwrap_or(0.0), config.providers.season.model)
+}
+
+// Latitudes below the equator get the opposite season
+pub fn season_at<Tz: TimeZone>(at: &DateTime<Tz>, latitude: f64, model: SeasonModel) -> Season {
+    let northern = match model {
+        SeasonModel::Astronomical => astronomical_season(&at.with_timezone(&Utc)),
+        // Months are local, March 1st starts spring wherever the player is
+        SeasonModel::Meteorological => match at.month() {
+            3..=5 => Season::Spring,
+            6..=8 => Season::Summer,
+            9..=11 => Season::Fall,
+            _ => Season::Winter,
+        },
+    };
+    if latitude < 0.0 { northern.opposite() } else { northern }
+}
+
+fn astronomical_season(at: &DateTime<Utc>) -> Season {
+    let year = at.year();
+    if *at < equinox_or_solstice(year, Turn::MarchEquinox) {
+        Season::Winter
+    } else if *at < equinox_or_solstice(year, Turn::JuneSolstice) {
+        Season::Spring
+    } else if *at < equinox_or_solstice(year, Turn::SeptemberEquinox) {
+        Season::Summer
+    } else if *at < equinox_or_solstice(year, Turn::DecemberSolstice) {
+        Season::Fall
+    } else {
+        Season::Winter
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Turn {
+    MarchEquinox,
+    JuneSolstice,
+    SeptemberEquinox,
+    DecemberSolstice,
+}
+
+// Periodic terms (A, B, C) from Meeus, Astronomical Algorithms, table 27.C
+const PERIODIC_TERMS: [(f64, f64, f64); 24] = [
+    (485.0, 324.96, 1934.136),
+    (203.0, 337.23, 32964.467),
+    (199.0, 342.08, 20.186),
+    (182.0, 27.85, 445267.112),
+    (156.0, 73.14, 45036.886),
+    (136.0, 171.52, 22518.443),
+    (77.0, 222.54, 65928.934),
+    (74.0, 296.72, 3034.906),
+    (70.0, 243.58, 9037.513),
+    (58.0, 119.81, 33718.147),
+    (52.0, 297.17, 150.678),
+    (50.0, 21.02, 2281.226),
+    (45.0, 247.54, 29929.562),
+    (44.0, 325.15, 31555.956),
+    (29.0, 60.93, 4443.417),
+    (18.0, 155.12, 67555.328),
+    (17.0, 288.79, 4562.452),
+    (16.0, 198.04, 62894.029),
+    (14.0, 199.76, 31436.921),
+    (12.0, 95.39, 14577.848),
+    (12.0, 287.11, 31931.756),
+    (12.0, 320.81, 34777.259),
+    (9.0, 227.73, 1222.114),
+    (8.0, 15.45, 16859.074),
+];
+
+// Terrestrial time runs about 69 seconds ahead of UTC this century
+const DELTA_T_SECS: f64 = 69.0;
+
+// Moment of the equinox or solstice, good to a couple of minutes. Uses Meeus' chapter 27
+// polynomials for 2000-3000, which still hold well enough for the late 1900s.
+pub fn equinox_or_solstice(year: i32, turn: Turn) -> DateTime<Utc> {
+    let y = (year as f64 - 2000.0) / 1000.0;
+    let coefficients = match turn {
+        Turn::MarchEquinox => [2451623.80984, 365242.37404, 0.05169, -0.00411, -0.00057],
+        Turn::JuneSolstice => [2451716.56767, 365241.62603, 0.00325, 0.00888, -0.00030],
+        Turn::SeptemberEquinox => [2451810.21715, 365242.01767, -0.11575, 0.00337, 0.00078],
+        Turn::DecemberSolstice => [2451900.05952, 365242.74049, -0.06223, -0.00823, 0.00032],
+    };
+    let jde0 = coefficients
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * y.powi(i as i32))
+        .sum::<f64>();
+
+    let t = (jde0 - 2451545.0) / 36525.0;
+    let w = (35999.373 * t - 2.47).to_radians();
+    let delta_lambda = 1.0 + 0.0334 * w.cos() + 0.0007 * (2.0 * w).cos();
+    let s: f64 = PERIODIC_TERMS
+        .iter()
+        .map(|(a, b, c)| a * (b + c * t).to_radians().cos())
+        .sum();
+    let jde = jde0 + 0.00001 * s / delta_lambda;
+
+    let unix_secs = (jde - 2440587.5) * 86400.0 - DELTA_T_SECS;
+    Utc.timestamp_opt(unix_secs.round() as i64, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+pub struct SeasonProvider;
+
+#[async_trait]
+impl FactorProvider for SeasonProvider {
+    fn name(&self) -> &'static str {
+        "season"
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(get_time::get_local_time().season)?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let season_mood = match serde_json::from_value(value.clone()).ok()? {
+            Season::Winter => MoodScores {
+                nostalgic: 1.0,
+                ..Default::default()
+            },
+            Season::Spring => MoodScores {
+                hopeful: 1.0,
+                ..Default::default()
+            },
+            Season::Summer => MoodScores {
+                happy: 1.0,
+                ..Default::default()
+            },
+            Season::Fall => MoodScores {
+                relaxing: 1.0,
+                ..Default::default()
+            },
+        };
+        Some(season_mood)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use chrono_tz::{America::Los_Angeles, Australia::Sydney};
+
+    const SAN_DIEGO: f64 = 32.88;
+    const SYDNEY: f64 = -33.87;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn assert_close(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        let diff = (actual - expected).num_seconds().abs();
+        assert!(diff <= 180, "{} is {}s away from {}", actual, diff, expected);
+    }
+
+    #[test]
+    fn turns_match_published_times() {
+        // USNO times for 2024 and 2025
+        assert_close(equinox_or_solstice(2024, Turn::MarchEquinox), utc(2024, 3, 20, 3, 6));
+        assert_close(equinox_or_solstice(2024, Turn::JuneSolstice), utc(2024, 6, 20, 20, 51));
+        assert_close(equinox_or_solstice(2024, Turn::SeptemberEquinox), utc(2024, 9, 22, 12, 44));
+        assert_close(equinox_or_solstice(2024, Turn::DecemberSolstice), utc(2024, 12, 21, 9, 21));
+        assert_close(equinox_or_solstice(2025, Turn::MarchEquinox), utc(2025, 3, 20, 9, 1));
+        assert_close(equinox_or_solstice(2025, Turn::DecemberSolstice), utc(2025, 12, 21, 15, 3));
+    }
+
+    #[test]
+    fn astronomical_boundaries_northern() {
+        let model = SeasonModel::Astronomical;
+        for (turn, before, after) in [
+            (Turn::MarchEquinox, Season::Winter, Season::Spring),
+            (Turn::JuneSolstice, Season::Spring, Season::Summer),
+            (Turn::SeptemberEquinox, Season::Summer, Season::Fall),
+            (Turn::DecemberSolstice, Season::Fall, Season::Winter),
+        ] {
+            let at = equinox_or_solstice(2026, turn);
+            let minute = Duration::minutes(1);
+            assert_eq!(season_at(&(at - minute), SAN_DIEGO, model), before, "{:?}", turn);
+            assert_eq!(season_at(&(at + minute), SAN_DIEGO, model), after, "{:?}", turn);
+        }
+    }
+
+    #[test]
+    fn astronomical_boundaries_southern() {
+        let model = SeasonModel::Astronomical;
+        let march = equinox_or_solstice(2026, Turn::MarchEquinox);
+        let minute = Duration::minutes(1);
+        assert_eq!(season_at(&(march - minute), SYDNEY, model), Season::Summer);
+        assert_eq!(season_at(&(march + minute), SYDNEY, model), Season::Fall);
+
+        let december = equinox_or_solstice(2026, Turn::DecemberSolstice);
+        assert_eq!(season_at(&(december - minute), SYDNEY, model), Season::Spring);
+        assert_eq!(season_at(&(december + minute), SYDNEY, model), Season::Summer);
+    }
+
+    #[test]
+    fn december_is_not_summer_in_the_north() {
+        for model in [SeasonModel::Astronomical, SeasonModel::Meteorological] {
+            for day in 1..=31 {
+                let at = Los_Angeles.with_ymd_and_hms(2026, 12, day, 12, 0, 0).unwrap();
+                let season = season_at(&at, SAN_DIEGO, model);
+                assert!(
+                    matches!(season, Season::Fall | Season::Winter),
+                    "2026-12-{} is {} ({:?})",
+                    day,
+                    season,
+                    model
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn new_year_is_winter_in_the_north() {
+        let at = Los_Angeles.with_ymd_and_hms(2027, 1, 1, 0, 30, 0).unwrap();
+        assert_eq!(season_at(&at, SAN_DIEGO, SeasonModel::Astronomical), Season::Winter);
+    }
+
+    #[test]
+    fn meteorological_boundaries_use_local_months() {
+        let model = SeasonModel::Meteorological;
+        let cases = [
+            ((2024, 2, 29), Season::Winter),
+            ((2024, 3, 1), Season::Spring),
+            ((2024, 5, 31), Season::Spring),
+            ((2024, 6, 1), Season::Summer),
+            ((2024, 8, 31), Season::Summer),
+            ((2024, 9, 1), Season::Fall),
+            ((2024, 11, 30), Season::Fall),
+            ((2024, 12, 1), Season::Winter),
+        ];
+        for ((y, m, d), season) in cases {
+            let start = Los_Angeles.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+            let end = Los_Angeles.with_ymd_and_hms(y, m, d, 23, 59, 0).unwrap();
+            assert_eq!(season_at(&start, SAN_DIEGO, model), season, "{}-{}-{}", y, m, d);
+            assert_eq!(season_at(&end, SAN_DIEGO, model), season, "{}-{}-{}", y, m, d);
+        }
+
+        // Local midnight on December 1st in Sydney is still November 30th in UTC
+        let sydney = Sydney.with_ymd_and_hms(2024, 12, 1, 0, 30, 0).unwrap();
+        assert_eq!(season_at(&sydney, SYDNEY, model), Season::Summer);
+        let sydney = Sydney.with_ymd_and_hms(2024, 11, 30, 23, 30, 0).unwrap();
+        assert_eq!(season_at(&sydney, SYDNEY, model), Season::Spring);
+    }
+
+    #[test]
+    fn season_serializes_as_lowercase_name() {
+        assert_eq!(serde_json::to_value(Season::Fall).unwrap(), Value::String("fall".into()));
+        assert_eq!(Season::Winter.to_string(), "winter");
+    }
+}
@@ -0,0 +1,302 @@
+use super::factor_provider::FactorProvider;
+use super::get_time;
+use super::mood::{MoodScores, normalize};
+use crate::config::get_config;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDate, Offset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+
+// Sun altitudes (degrees) at which the events happen, -0.833 allows for refraction and the disc size
+const SUNRISE_ALTITUDE: f64 = -0.833;
+const CIVIL_TWILIGHT_ALTITUDE: f64 = -6.0;
+const GOLDEN_HOUR_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SunPhase {
+    Night,
+    // Civil twilight before sunrise
+    Dawn,
+    MorningGoldenHour,
+    Day,
+    EveningGoldenHour,
+    // Civil twilight after sunset
+    Dusk,
+}
+
+// Times are None on days the sun never crosses that altitude (polar day or night)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sun {
+    pub civil_dawn: Option<DateTime<FixedOffset>>,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
+    pub civil_dusk: Option<DateTime<FixedOffset>>,
+    pub day_length_minutes: i64,
+    pub minutes_since_sunrise: Option<i64>,
+    pub minutes_until_sunset: Option<i64>,
+    pub phase: SunPhase,
+    pub is_daytime: bool,
+}
+
+// When the sun crosses `altitude` on the given day
+enum Crossing {
+    Times(DateTime<Utc>, DateTime<Utc>),
+    AlwaysAbove,
+    AlwaysBelow,
+}
+
+// Sunrise equation after the NOAA/Meeus approximations, accurate to a minute or two
+fn crossing(date: NaiveDate, latitude: f64, longitude: f64, altitude: f64) -> Crossing {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default();
+    let n = (date - epoch).num_days() as f64;
+    let mean_noon = n - longitude / 360.0;
+
+    let anomaly = (357.5291 + 0.98560028 * mean_noon).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * anomaly.sin()
+        + 0.02 * (2.0 * anomaly).sin()
+        + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = 2451545.0 + mean_noon + 0.0053 * anomaly.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let phi = latitude.to_radians();
+    let cos_hour_angle = (altitude.to_radians().sin() - phi.sin() * declination.sin())
+        / (phi.cos() * declination.cos());
+    if cos_hour_angle < -1.0 {
+        return Crossing::AlwaysAbove;
+    }
+    if cos_hour_angle > 1.0 {
+        return Crossing::AlwaysBelow;
+    }
+
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    Crossing::Times(julian_to_utc(transit - half_day), julian_to_utc(transit + half_day))
+}
+
+fn julian_to_utc(jd: f64) -> DateTime<Utc> {
+    let unix_secs = (jd - 2440587.5) * 86400.0;
+    Utc.timestamp_opt(unix_secs.round() as i64, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+// Sun times for `now`'s local date at the given coordinates
+pub fn sun_at<Tz: TimeZone>(now: &DateTime<Tz>, latitude: f64, longitude: f64) -> Sun {
+    let date = now.date_naive();
+    let offset = now.offset().fix();
+    let local = |t: DateTime<Utc>| t.with_timezone(&offset);
+    let now = now.with_timezone(&Utc);
+
+    let (sunrise, sunset, always_up) =
+        match crossing(date, latitude, longitude, SUNRISE_ALTITUDE) {
+            Crossing::Times(rise, set) => (Some(rise), Some(set), false),
+            Crossing::AlwaysAbove => (None, None, true),
+            Crossing::AlwaysBelow => (None, None, false),
+        };
+    let (civil_dawn, civil_dusk) =
+        match crossing(date, latitude, longitude, CIVIL_TWILIGHT_ALTITUDE) {
+            Crossing::Times(dawn, dusk) => (Some(dawn), Some(dusk)),
+            _ => (None, None),
+        };
+
+    let phase = match (sunrise, sunset) {
+        (Some(rise), Some(set)) => {
+            let golden = chrono::Duration::minutes(GOLDEN_HOUR_MINUTES);
+            if now >= rise && now < set {
+                if now < rise + golden {
+                    SunPhase::MorningGoldenHour
+                } else if now >= set - golden {
+                    SunPhase::EveningGoldenHour
+                } else {
+                    SunPhase::Day
+                }
+            } else if civil_dawn.is_some_and(|dawn| now >= dawn && now < rise) {
+                SunPhase::Dawn
+            } else if civil_dusk.is_some_and(|dusk| now >= set && now < dusk) {
+                SunPhase::Dusk
+            } else {
+                SunPhase::Night
+            }
+        }
+        _ if always_up => SunPhase::Day,
+        _ => SunPhase::Night,
+    };
+
+    let day_length_minutes = match (sunrise, sunset) {
+        (Some(rise), Some(set)) => (set - rise).num_minutes(),
+        _ if always_up => 24 * 60,
+        _ => 0,
+    };
+
+    Sun {
+        civil_dawn: civil_dawn.map(local),
+        sunrise: sunrise.map(local),
+        sunset: sunset.map(local),
+        civil_dusk: civil_dusk.map(local),
+        day_length_minutes,
+        minutes_since_sunrise: sunrise.map(|rise| (now - rise).num_minutes()),
+        minutes_until_sunset: sunset.map(|set| (set - now).num_minutes()),
+        is_daytime: matches!(
+            phase,
+            SunPhase::MorningGoldenHour | SunPhase::Day | SunPhase::EveningGoldenHour
+        ),
+        phase,
+    }
+}
+
+// The sun right now at the configured location, None without coordinates
+pub fn get_sun() -> Option<Sun> {
+    let location = &get_config().location;
+    let (latitude, longitude) = (location.latitude?, location.longitude?);
+    Some(sun_at(&get_time::get_local_dt(), latitude, longitude))
+}
+
+pub struct SunProvider;
+
+#[async_trait]
+impl FactorProvider for SunProvider {
+    fn name(&self) -> &'static str {
+        "sun"
+    }
+
+    fn enabled(&self) -> bool {
+        let location = &get_config().location;
+        location.latitude.is_some() && location.longitude.is_some()
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        let sun = get_sun().ok_or("location.latitude and longitude are not set")?;
+        Ok(serde_json::to_value(sun)?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let sun: Sun = serde_json::from_value(value.clone()).ok()?;
+
+        let sun_mood = match sun.phase {
+            SunPhase::Night => MoodScores {
+                mysterious: 0.6,
+                melancholic: 0.4,
+                ..Default::default()
+            },
+            SunPhase::Dawn => MoodScores {
+                hopeful: 0.6,
+                mysterious: 0.4,
+                ..Default::default()
+            },
+            SunPhase::MorningGoldenHour => MoodScores {
+                hopeful: 0.5,
+                happy: 0.5,
+                ..Default::default()
+            },
+            SunPhase::Day => MoodScores {
+                happy: 0.5,
+                relaxing: 0.5,
+                ..Default::default()
+            },
+            SunPhase::EveningGoldenHour => MoodScores {
+                nostalgic: 0.6,
+                relaxing: 0.4,
+                ..Default::default()
+            },
+            SunPhase::Dusk => MoodScores {
+                melancholic: 0.5,
+                nostalgic: 0.5,
+                ..Default::default()
+            },
+        };
+        Some(normalize(sun_mood))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use chrono_tz::{America::New_York, Europe::London, Europe::Oslo};
+
+    // Tromsø, above the Arctic Circle
+    const TROMSO: (f64, f64) = (69.65, 18.96);
+
+    fn assert_close<Tz: TimeZone>(actual: Option<DateTime<FixedOffset>>, expected: DateTime<Tz>) {
+        let actual = actual.expect("a time");
+        let diff = (actual.with_timezone(&Utc) - expected.with_timezone(&Utc)).num_seconds().abs();
+        assert!(diff <= 180, "{} is {}s away from {:?}", actual, diff, expected);
+    }
+
+    #[test]
+    fn times_match_published_times() {
+        // USNO times for New York on the 2024 June solstice
+        let ny = |h, m| New_York.with_ymd_and_hms(2024, 6, 20, h, m, 0).unwrap();
+        let sun = sun_at(&ny(12, 0), 40.7128, -74.0060);
+        assert_close(sun.civil_dawn, ny(4, 52));
+        assert_close(sun.sunrise, ny(5, 25));
+        assert_close(sun.sunset, ny(20, 31));
+        assert_close(sun.civil_dusk, ny(21, 4));
+
+        // And London on the 2024 March equinox
+        let london = |h, m| London.with_ymd_and_hms(2024, 3, 20, h, m, 0).unwrap();
+        let sun = sun_at(&london(12, 0), 51.5074, -0.1278);
+        assert_close(sun.civil_dawn, london(5, 30));
+        assert_close(sun.sunrise, london(6, 2));
+        assert_close(sun.sunset, london(18, 13));
+        assert_close(sun.civil_dusk, london(18, 46));
+        // 12h 11m
+        assert!((sun.day_length_minutes - 731).abs() <= 3);
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        let (lat, lon) = TROMSO;
+        let june = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let december = NaiveDate::from_ymd_opt(2026, 12, 21).unwrap();
+        assert!(matches!(crossing(june, lat, lon, SUNRISE_ALTITUDE), Crossing::AlwaysAbove));
+        assert!(matches!(crossing(december, lat, lon, SUNRISE_ALTITUDE), Crossing::AlwaysBelow));
+
+        let midnight_sun = sun_at(&Oslo.with_ymd_and_hms(2026, 6, 21, 0, 30, 0).unwrap(), lat, lon);
+        assert_eq!(midnight_sun.phase, SunPhase::Day);
+        assert!(midnight_sun.is_daytime);
+        assert_eq!(midnight_sun.day_length_minutes, 24 * 60);
+        assert_eq!(midnight_sun.sunrise, None);
+
+        let polar_night = sun_at(&Oslo.with_ymd_and_hms(2026, 12, 21, 12, 0, 0).unwrap(), lat, lon);
+        assert_eq!(polar_night.phase, SunPhase::Night);
+        assert!(!polar_night.is_daytime);
+        assert_eq!(polar_night.day_length_minutes, 0);
+        // The sun still gets within 6 degrees of the horizon at noon
+        assert!(polar_night.civil_dawn.is_some());
+    }
+
+    #[test]
+    fn phases_change_at_the_golden_hour_edges() {
+        let (lat, lon) = (51.5074, -0.1278);
+        let noon = London.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap();
+        let sun = sun_at(&noon, lat, lon);
+        let (dawn, rise) = (sun.civil_dawn.unwrap(), sun.sunrise.unwrap());
+        let (set, dusk) = (sun.sunset.unwrap(), sun.civil_dusk.unwrap());
+        let minute = Duration::minutes(1);
+        let golden = Duration::minutes(GOLDEN_HOUR_MINUTES);
+
+        let cases = [
+            (dawn - minute, SunPhase::Night),
+            (dawn + minute, SunPhase::Dawn),
+            (rise - minute, SunPhase::Dawn),
+            (rise + minute, SunPhase::MorningGoldenHour),
+            (rise + golden - minute, SunPhase::MorningGoldenHour),
+            (rise + golden + minute, SunPhase::Day),
+            (set - golden - minute, SunPhase::Day),
+            (set - golden + minute, SunPhase::EveningGoldenHour),
+            (set - minute, SunPhase::EveningGoldenHour),
+            (set + minute, SunPhase::Dusk),
+            (dusk - minute, SunPhase::Dusk),
+            (dusk + minute, SunPhase::Night),
+        ];
+        for (at, phase) in cases {
+            assert_eq!(sun_at(&at, lat, lon).phase, phase, "{}", at);
+        }
+    }
+}
@@ -1,67 +1,107 @@
-use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
-use chrono_tz::America::Los_Angeles;
+use super::factor_provider::FactorProvider;
+use super::get_season::{Season, current_season};
+use super::mood::{MoodScores, normalize};
+use crate::config::get_config;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Time{
     pub min: u8,
     pub hour: u8,
     pub day: u8,
     pub month: u8,
     pub year: u16,
-    pub season: String
+    pub season: Season,
 }
 
-pub fn get_pacific_dt() -> DateTime<chrono_tz::Tz> {
-    let utc: DateTime<Utc> = Utc::now();
-
-    let pacific: DateTime<chrono_tz::Tz> = Los_Angeles.from_utc_datetime(&utc.naive_utc());
+// The configured location.timezone, which startup validation has already checked
+pub fn local_timezone() -> Tz {
+    get_config().location.timezone.parse().unwrap_or(Tz::UTC)
+}
 
-    pacific
+pub fn get_local_dt() -> DateTime<Tz> {
+    Utc::now().with_timezone(&local_timezone())
 }
 
-pub fn get_pacific_time() -> Time {
-    let t = get_pacific_dt();
+pub fn get_local_time() -> Time {
+    let t = get_local_dt();
     Time {
-        min: get_pacific_minute(t), 
-        hour: get_pacific_hour(t),
-        day: get_pacific_day(t),
-        month: get_pacific_month(t),
-        year: get_pacific_year(t),
-        season: match get_pacific_month(t) {
-            11 | 0..=2 => "winter".to_string(),
-            3..=5 => "spring".to_string(),
-            6..=8 => "summer".to_string(),
-            9 | 10 => "fall".to_string(),
-            _ => "summer".to_string()
-        }
+        min: get_minute(t),
+        hour: get_hour(t),
+        day: get_day(t),
+        month: get_month(t),
+        year: get_year(t),
+        season: current_season(&t),
     }
 }
 
-pub fn get_pacific_year(cur_dt: DateTime<chrono_tz::Tz>) -> u16 {
+pub fn get_year(cur_dt: DateTime<Tz>) -> u16 {
     cur_dt.year() as u16
 }
 
-pub fn get_pacific_month(cur_dt: DateTime<chrono_tz::Tz>) -> u8 {
+pub fn get_month(cur_dt: DateTime<Tz>) -> u8 {
     cur_dt.month() as u8
 }
 
-pub fn get_pacific_day(cur_dt: DateTime<chrono_tz::Tz>) -> u8 {
+pub fn get_day(cur_dt: DateTime<Tz>) -> u8 {
     cur_dt.day() as u8
 }
 
-pub fn get_pacific_hour(cur_dt: DateTime<chrono_tz::Tz>) -> u8 {
+pub fn get_hour(cur_dt: DateTime<Tz>) -> u8 {
     cur_dt.hour() as u8
 }
 
-pub fn get_pacific_minute(cur_dt: DateTime<chrono_tz::Tz>) -> u8 {
+pub fn get_minute(cur_dt: DateTime<Tz>) -> u8 {
     cur_dt.minute() as u8
 }
-pub fn get_time_of_day(cur_hour: u8, cur_min: u8) -> f32 {
-    let cur_time: f32 = cur_hour as f32 + (cur_min as f32 / 60.);
-    cur_time
-}
 
-// Count fri,sat,sun as weekend
-pub fn is_weekend(cur_dt: DateTime<chrono_tz::Tz>) -> bool {
-    let day = cur_dt.weekday().num_days_from_monday();
-    return day == 4 || day == 5 || day == 6;
-}
\ No newline at end of file
+pub struct TimeProvider;
+
+#[async_trait]
+impl FactorProvider for TimeProvider {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(get_local_time())?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let time: Time = serde_json::from_value(value.clone()).ok()?;
+
+        let time_mood = match time.hour {
+            5..=11 => MoodScores {
+                happy: 0.5,
+                hopeful: 0.5,
+                ..Default::default()
+            },
+            12..=14 => MoodScores {
+                happy: 0.4,
+                relaxing: 0.6,
+                ..Default::default()
+            },
+            15..=17 => MoodScores {
+                nostalgic: 0.5,
+                relaxing: 0.5,
+                ..Default::default()
+            },
+            18..=21 => MoodScores {
+                relaxing: 0.6,
+                melancholic: 0.4,
+                ..Default::default()
+            },
+            _ => MoodScores {
+                mysterious: 0.7,
+                melancholic: 0.3,
+                ..Default::default()
+            },
+        };
+        Some(normalize(time_mood))
+    }
+}
@@ -0,0 +1,11 @@
+use reqwest::Client;
+use std::time::Duration;
+
+// A source that hangs instead of failing would hold up the song, and the stale cache
+// fallback only gets a chance once the request gives up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Every factor that goes over the network builds its client here
+pub fn client() -> Result<Client, reqwest::Error> {
+    Client::builder().timeout(REQUEST_TIMEOUT).build()
+}
@@ -1,7 +1,17 @@
 pub mod get_mercury_retrograde;
+pub mod get_moon;
+pub mod get_occupancy;
+pub mod get_quarter;
+pub mod get_holidays;
+pub mod get_season;
+pub mod get_sun;
 pub mod get_time;
-pub mod get_weather_data;
+pub mod http;
+pub mod weather;
 pub mod get_market_data;
+pub mod factor_cache;
+pub mod factor_provider;
+pub mod mood;
 mod get_external_factors;
 
-pub use get_external_factors::{get_external_factors, ExternalFactors};
\ No newline at end of file
+pub use get_external_factors::{get_external_factors, ExternalFactors, FactorRegistry};
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+// Missing fields read as zero, so configs can write e.g. `mood = { nostalgic = 1.0 }`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoodScores {
+    pub happy: f32,
+    pub melancholic: f32,
+    pub hopeful: f32,
+    pub nostalgic: f32,
+    pub mysterious: f32,
+    pub relaxing: f32,
+}
+
+pub fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+pub fn normalize(mut mood: MoodScores) -> MoodScores {
+    let total = mood.happy
+        + mood.melancholic
+        + mood.hopeful
+        + mood.nostalgic
+        + mood.mysterious
+        + mood.relaxing;
+    if total != 0.0 {
+        mood.happy /= total;
+        mood.melancholic /= total;
+        mood.hopeful /= total;
+        mood.nostalgic /= total;
+        mood.mysterious /= total;
+        mood.relaxing /= total;
+    }
+    mood
+}
+
+pub fn sum_moods(a: MoodScores, b: MoodScores) -> MoodScores {
+    MoodScores {
+        happy: a.happy + b.happy,
+        melancholic: a.melancholic + b.melancholic,
+        hopeful: a.hopeful + b.hopeful,
+        nostalgic: a.nostalgic + b.nostalgic,
+        mysterious: a.mysterious + b.mysterious,
+        relaxing: a.relaxing + b.relaxing,
+    }
+}
+
+pub fn scale_mood(mood: MoodScores, factor: f32) -> MoodScores {
+    MoodScores {
+        happy: mood.happy * factor,
+        melancholic: mood.melancholic * factor,
+        hopeful: mood.hopeful * factor,
+        nostalgic: mood.nostalgic * factor,
+        mysterious: mood.mysterious * factor,
+        relaxing: mood.relaxing * factor,
+    }
+}
+
+pub fn average_mood(mood: MoodScores, count: f32) -> MoodScores {
+    scale_mood(mood, 1.0 / count)
+}
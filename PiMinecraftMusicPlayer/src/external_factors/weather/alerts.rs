@@ -0,0 +1,404 @@
+use super::super::factor_provider::FactorProvider;
+use super::super::{get_time, http};
+use super::super::mood::{MoodScores, normalize, scale_mood, sum_moods};
+use super::super::FactorRegistry;
+use crate::config::{AlertsProviderConfig, Config, WeatherBackend, get_config};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, TimeZone};
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Severity {
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    Heat,
+    Cold,
+    Wind,
+    Fire,
+    Flood,
+    Storm,
+    AirQuality,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    // weather.gov's event name, e.g. "Heat Advisory"
+    pub event: String,
+    pub kind: AlertKind,
+    pub severity: Severity,
+    pub headline: Option<String>,
+    // When the hazard ends, or when the message expires for alerts without an end
+    pub ends: Option<DateTime<FixedOffset>>,
+}
+
+// What the active alerts allow playback to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertPlayback {
+    Normal,
+    // Fraction of full volume, and the event that asked for it
+    Soften { volume: f32, event: String },
+    Suppress { event: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertArea {
+    Point(f64, f64),
+    // Forecast zone or county code
+    Zone(String),
+}
+
+// api.weather.gov's active alerts for one point or zone
+pub struct WeatherGovAlerts {
+    pub base_url: String,
+    pub user_agent: String,
+    pub area: AlertArea,
+}
+
+impl Severity {
+    // How strongly an alert's mood counts
+    fn weight(self) -> f32 {
+        match self {
+            Severity::Minor => 0.25,
+            Severity::Moderate | Severity::Unknown => 0.5,
+            Severity::Severe => 0.75,
+            Severity::Extreme => 1.0,
+        }
+    }
+}
+
+impl AlertKind {
+    // Checked in order, so "Winter Storm Warning" is cold, "Dust Storm Warning" air quality
+    // and "Wind Chill Advisory" cold rather than wind
+    pub fn from_event(event: &str) -> AlertKind {
+        let event = event.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| event.contains(w));
+        if has(&["air quality", "smoke", "dust", "ashfall"]) {
+            AlertKind::AirQuality
+        } else if has(&["fire", "red flag"]) {
+            AlertKind::Fire
+        } else if has(&["heat"]) {
+            AlertKind::Heat
+        } else if has(&["winter", "blizzard", "snow", "ice", "freez", "frost", "cold", "chill"]) {
+            AlertKind::Cold
+        } else if has(&["flood", "surf", "tsunami", "storm surge"]) {
+            AlertKind::Flood
+        } else if has(&["thunderstorm", "tornado", "hurricane", "tropical", "storm"]) {
+            AlertKind::Storm
+        } else if has(&["wind", "gale"]) {
+            AlertKind::Wind
+        } else {
+            AlertKind::Other
+        }
+    }
+
+    fn mood(self, config: &AlertsProviderConfig) -> &MoodScores {
+        match self {
+            AlertKind::Heat => &config.heat_mood,
+            AlertKind::Cold => &config.cold_mood,
+            AlertKind::Wind => &config.wind_mood,
+            AlertKind::Fire => &config.fire_mood,
+            AlertKind::Flood => &config.flood_mood,
+            AlertKind::Storm => &config.storm_mood,
+            AlertKind::AirQuality => &config.air_quality_mood,
+            AlertKind::Other => &config.other_mood,
+        }
+    }
+}
+
+impl Alert {
+    pub fn is_active<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> bool {
+        self.ends.is_none_or(|ends| ends > *now)
+    }
+}
+
+impl WeatherGovAlerts {
+    pub async fn active(&self) -> Result<Vec<Alert>, Box<dyn Error>> {
+        let url = format!("{}/alerts/active", self.base_url.trim_end_matches('/'));
+        let area = match &self.area {
+            AlertArea::Point(lat, lon) => ("point", format!("{:.4},{:.4}", lat, lon)),
+            AlertArea::Zone(zone) => ("zone", zone.trim().to_uppercase()),
+        };
+        let body = http::client()?
+            .get(url)
+            .query(&[area, ("status", "actual".to_string())])
+            .header(USER_AGENT, &self.user_agent)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let v: Value = serde_json::from_str(&body)?;
+        let features = v["features"].as_array().ok_or("Missing features")?;
+        Ok(features.iter().filter_map(|f| parse_alert(&f["properties"])).collect())
+    }
+}
+
+// None for tests, exercises and cancellations, which have nothing to act on
+fn parse_alert(properties: &Value) -> Option<Alert> {
+    if properties["status"].as_str() != Some("Actual")
+        || properties["messageType"].as_str() == Some("Cancel")
+    {
+        return None;
+    }
+    let event = properties["event"].as_str()?.to_string();
+    let ends = properties["ends"]
+        .as_str()
+        .or(properties["expires"].as_str())
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+    Some(Alert {
+        kind: AlertKind::from_event(&event),
+        severity: serde_json::from_value(properties["severity"].clone()).unwrap_or_default(),
+        headline: properties["headline"].as_str().map(|h| h.to_string()),
+        ends,
+        event,
+    })
+}
+
+// The configured zone, otherwise the location's point. weather.gov only covers the US, so
+// the point is only used with the weather_gov backend. None when there is neither.
+pub fn alert_source(config: &Config) -> Option<WeatherGovAlerts> {
+    let location = &config.location;
+    let area = match &config.providers.alerts.zone {
+        Some(zone) => AlertArea::Zone(zone.clone()),
+        None if config.providers.weather.backend != WeatherBackend::WeatherGov => return None,
+        None => {
+            let (lat, lon) = location.latitude.zip(location.longitude)?;
+            AlertArea::Point(lat, lon)
+        }
+    };
+    Some(WeatherGovAlerts {
+        base_url: config.providers.weather.weather_gov_url.clone(),
+        user_agent: config.providers.weather.user_agent.clone(),
+        area,
+    })
+}
+
+// Suppressing wins over softening
+fn playback_for<Tz: TimeZone>(
+    alerts: &[Alert],
+    config: &AlertsProviderConfig,
+    now: &DateTime<Tz>,
+) -> AlertPlayback {
+    let listed = |names: &[String], event: &str| names.iter().any(|n| n.eq_ignore_ascii_case(event));
+    let active: Vec<&Alert> = alerts.iter().filter(|a| a.is_active(now)).collect();
+
+    if let Some(alert) = active.iter().find(|a| listed(&config.suppress, &a.event)) {
+        return AlertPlayback::Suppress {
+            event: alert.event.clone(),
+        };
+    }
+    match active.iter().find(|a| listed(&config.soften, &a.event)) {
+        Some(alert) => AlertPlayback::Soften {
+            volume: config.soften_volume,
+            event: alert.event.clone(),
+        },
+        None => AlertPlayback::Normal,
+    }
+}
+
+// Every active alert's mood weighted by its severity, None without any
+fn alerts_mood<Tz: TimeZone>(
+    alerts: &[Alert],
+    config: &AlertsProviderConfig,
+    now: &DateTime<Tz>,
+) -> Option<MoodScores> {
+    let alert_mood = alerts
+        .iter()
+        .filter(|a| a.is_active(now))
+        .map(|a| scale_mood(a.kind.mood(config).clone(), a.severity.weight()))
+        .reduce(sum_moods)?;
+    Some(normalize(alert_mood))
+}
+
+// How to play right now, from the same cached reading the mood uses
+pub async fn alert_playback(registry: &FactorRegistry) -> AlertPlayback {
+    let Some(value) = registry.read_current(AlertsProvider.name()).await else {
+        return AlertPlayback::Normal;
+    };
+    match serde_json::from_value::<Vec<Alert>>(value) {
+        Ok(alerts) => {
+            playback_for(&alerts, &get_config().providers.alerts, &get_time::get_local_dt())
+        }
+        Err(e) => {
+            eprintln!("Failed to read the cached weather alerts, playing normally: {}", e);
+            AlertPlayback::Normal
+        }
+    }
+}
+
+pub struct AlertsProvider;
+
+#[async_trait]
+impl FactorProvider for AlertsProvider {
+    fn name(&self) -> &'static str {
+        "alerts"
+    }
+
+    fn enabled(&self) -> bool {
+        alert_source(get_config()).is_some()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(10 * 60))
+    }
+
+    fn cache_config(&self) -> Value {
+        let config = get_config();
+        let location = (config.location.latitude, config.location.longitude);
+        let weather = &config.providers.weather;
+        serde_json::to_value((&config.providers.alerts.zone, location, weather)).unwrap_or_default()
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        let source = alert_source(get_config()).ok_or("Missing zone or latitude and longitude")?;
+        Ok(serde_json::to_value(source.active().await?)?)
+    }
+
+    // Alerts that ended since the reading was cached no longer count
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let alerts: Vec<Alert> = serde_json::from_value(value.clone()).ok()?;
+        alerts_mood(&alerts, &get_config().providers.alerts, &get_time::get_local_dt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub_server::StubServer;
+    use super::*;
+
+    const ALERTS: &str = include_str!("../../../testdata/weather_gov_alerts.json");
+    const EVACUATION: &str = include_str!("../../../testdata/weather_gov_alerts_evacuation.json");
+
+    fn source(server: &StubServer, area: AlertArea) -> WeatherGovAlerts {
+        WeatherGovAlerts {
+            base_url: server.url(),
+            user_agent: "test".to_string(),
+            area,
+        }
+    }
+
+    fn at(time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(time).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_actual_alerts_for_a_point() {
+        let server = StubServer::start();
+        server.route("/alerts/active", 200, ALERTS);
+
+        let alerts = source(&server, AlertArea::Point(32.8801, -117.234)).active().await.unwrap();
+        // The test message is skipped
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].event, "Red Flag Warning");
+        assert_eq!(alerts[0].kind, AlertKind::Fire);
+        assert_eq!(alerts[0].severity, Severity::Severe);
+        assert_eq!(alerts[0].ends, Some(at("2026-10-19T18:00:00-07:00")));
+        assert_eq!(alerts[1].kind, AlertKind::Heat);
+        // No end, so the message's expiry
+        assert_eq!(alerts[1].ends, Some(at("2026-10-18T14:00:00-07:00")));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("point=32.8801%2C-117.2340"));
+        assert!(requests[0].contains("status=actual"));
+    }
+
+    #[tokio::test]
+    async fn reads_alerts_for_a_zone() {
+        let server = StubServer::start();
+        server.route("/alerts/active", 200, EVACUATION);
+
+        let alerts = source(&server, AlertArea::Zone("caz043".to_string())).active().await.unwrap();
+        assert_eq!(alerts[0].event, "Evacuation Immediate");
+        assert_eq!(alerts[0].severity, Severity::Extreme);
+        assert_eq!(alerts[1].kind, AlertKind::AirQuality);
+        assert_eq!(alerts[1].severity, Severity::Unknown);
+        assert!(server.requests()[0].contains("zone=CAZ043"));
+    }
+
+    #[tokio::test]
+    async fn fails_on_server_errors() {
+        let server = StubServer::start();
+        server.route("/alerts/active", 503, r#"{"title": "Service Unavailable"}"#);
+
+        assert!(source(&server, AlertArea::Zone("CAZ043".to_string())).active().await.is_err());
+    }
+
+    #[test]
+    fn severe_alerts_suppress_or_soften_playback() {
+        let config = AlertsProviderConfig::default();
+        let now = at("2026-10-18T15:00:00-07:00");
+        let alerts = |body: &str| -> Vec<Alert> {
+            let v: Value = serde_json::from_str(body).unwrap();
+            let features = v["features"].as_array().unwrap();
+            features.iter().filter_map(|f| parse_alert(&f["properties"])).collect()
+        };
+
+        assert_eq!(
+            playback_for(&alerts(EVACUATION), &config, &now),
+            AlertPlayback::Suppress {
+                event: "Evacuation Immediate".to_string()
+            }
+        );
+        assert_eq!(
+            playback_for(&alerts(ALERTS), &config, &now),
+            AlertPlayback::Soften {
+                volume: 0.5,
+                event: "Red Flag Warning".to_string()
+            }
+        );
+        // After the red flag warning ends
+        let later = at("2026-10-19T19:00:00-07:00");
+        assert_eq!(playback_for(&alerts(ALERTS), &config, &later), AlertPlayback::Normal);
+        assert_eq!(playback_for(&[], &config, &now), AlertPlayback::Normal);
+    }
+
+    #[test]
+    fn expired_alerts_have_no_mood() {
+        let config = AlertsProviderConfig::default();
+        let v: Value = serde_json::from_str(ALERTS).unwrap();
+        let heat = parse_alert(&v["features"][1]["properties"]).unwrap();
+
+        let morning = at("2026-10-18T12:00:00-07:00");
+        let mood = alerts_mood(std::slice::from_ref(&heat), &config, &morning).unwrap();
+        assert!(mood.relaxing > mood.melancholic);
+        assert!(alerts_mood(&[heat], &config, &at("2026-10-18T15:00:00-07:00")).is_none());
+    }
+
+    #[test]
+    fn events_map_to_kinds() {
+        let cases = [
+            ("Excessive Heat Warning", AlertKind::Heat),
+            ("Wind Chill Advisory", AlertKind::Cold),
+            ("Winter Storm Watch", AlertKind::Cold),
+            ("Dust Storm Warning", AlertKind::AirQuality),
+            ("Dense Smoke Advisory", AlertKind::AirQuality),
+            ("Fire Weather Watch", AlertKind::Fire),
+            ("Flash Flood Emergency", AlertKind::Flood),
+            ("High Surf Advisory", AlertKind::Flood),
+            ("Tornado Warning", AlertKind::Storm),
+            ("Tropical Storm Warning", AlertKind::Storm),
+            ("Wind Advisory", AlertKind::Wind),
+            ("Evacuation Immediate", AlertKind::Other),
+        ];
+        for (event, kind) in cases {
+            assert_eq!(AlertKind::from_event(event), kind, "{}", event);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::super::mood::MoodScores;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherCondition {
+    // Also mostly clear or mostly sunny
+    Clear,
+    PartlyCloudy,
+    // Mostly cloudy or overcast
+    Cloudy,
+    Fog,
+    // Haze, smoke or dust
+    Haze,
+    Drizzle,
+    Rain,
+    Showers,
+    Thunderstorm,
+    Snow,
+    // Sleet, freezing rain or a rain and snow mix
+    Sleet,
+    // Windy or breezy with no weather worth mentioning
+    Wind,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Intensity {
+    Light,
+    #[default]
+    Moderate,
+    Heavy,
+}
+
+impl WeatherCondition {
+    pub fn mood(self) -> MoodScores {
+        let (happy, melancholic, hopeful, nostalgic, mysterious, relaxing) = match self {
+            WeatherCondition::Clear => (0.6, 0.0, 0.4, 0.0, 0.0, 0.0),
+            WeatherCondition::PartlyCloudy => (0.4, 0.0, 0.3, 0.0, 0.0, 0.3),
+            WeatherCondition::Cloudy => (0.0, 0.5, 0.0, 0.5, 0.0, 0.0),
+            WeatherCondition::Fog => (0.0, 0.0, 0.0, 0.3, 0.7, 0.0),
+            WeatherCondition::Haze => (0.0, 0.5, 0.0, 0.0, 0.5, 0.0),
+            WeatherCondition::Drizzle => (0.0, 0.0, 0.0, 0.5, 0.0, 0.5),
+            WeatherCondition::Rain => (0.0, 0.6, 0.0, 0.0, 0.0, 0.4),
+            WeatherCondition::Showers => (0.0, 0.4, 0.3, 0.0, 0.0, 0.3),
+            WeatherCondition::Thunderstorm => (0.0, 0.4, 0.0, 0.0, 0.6, 0.0),
+            WeatherCondition::Snow => (0.2, 0.0, 0.0, 0.5, 0.0, 0.3),
+            WeatherCondition::Sleet => (0.0, 0.7, 0.0, 0.3, 0.0, 0.0),
+            WeatherCondition::Wind => (0.0, 0.0, 0.0, 0.4, 0.6, 0.0),
+            WeatherCondition::Unknown => (0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+        };
+        MoodScores {
+            happy,
+            melancholic,
+            hopeful,
+            nostalgic,
+            mysterious,
+            relaxing,
+        }
+    }
+}
+
+impl Intensity {
+    // How strongly the condition's mood counts
+    pub fn scale(self) -> f32 {
+        match self {
+            Intensity::Light => 0.5,
+            Intensity::Moderate => 1.0,
+            Intensity::Heavy => 1.5,
+        }
+    }
+}
+
+// Reads weather.gov's forecast wording, e.g. "Chance Light Rain" or "Patchy Fog".
+// Checked from the most to the least significant weather, so "Rain And Snow" is sleet
+// and "Showers And Thunderstorms" a thunderstorm.
+pub fn parse_forecast(text: &str) -> (WeatherCondition, Intensity) {
+    let text = text.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| text.contains(w));
+
+    let condition = if has(&["thunder", "t-storm", "tstorm"]) {
+        WeatherCondition::Thunderstorm
+    } else if has(&["sleet", "freezing", "ice pellets", "wintry mix"])
+        || (has(&["snow"]) && has(&["rain"]))
+    {
+        WeatherCondition::Sleet
+    } else if has(&["snow", "flurries", "blizzard"]) {
+        WeatherCondition::Snow
+    } else if has(&["shower"]) {
+        WeatherCondition::Showers
+    } else if has(&["rain"]) {
+        WeatherCondition::Rain
+    } else if has(&["drizzle"]) {
+        WeatherCondition::Drizzle
+    } else if has(&["fog", "mist"]) {
+        WeatherCondition::Fog
+    } else if has(&["haze", "smoke", "dust", "sand"]) {
+        WeatherCondition::Haze
+    } else if has(&["windy", "breezy", "blustery", "gust"]) {
+        WeatherCondition::Wind
+    } else if has(&["mostly sunny", "mostly clear"]) {
+        WeatherCondition::Clear
+    } else if has(&["partly"]) {
+        WeatherCondition::PartlyCloudy
+    } else if has(&["cloudy", "overcast"]) {
+        WeatherCondition::Cloudy
+    } else if has(&["sunny", "clear", "fair"]) {
+        WeatherCondition::Clear
+    } else {
+        WeatherCondition::Unknown
+    };
+
+    // Whole words, "slight chance" is not light rain
+    let has_word = |words: &[&str]| text.split_whitespace().any(|w| words.contains(&w));
+    let intensity = if has_word(&["heavy", "severe", "blizzard", "windy"]) {
+        Intensity::Heavy
+    } else if has_word(&["light", "patchy", "breezy", "flurries"]) {
+        Intensity::Light
+    } else {
+        Intensity::Moderate
+    };
+
+    (condition, intensity)
+}
+
+// Rough sky cover (percent) for weather.gov's sky wording, which its hourly forecast
+// has no number for. None when the wording is about other weather.
+pub fn sky_cover_estimate(text: &str) -> Option<f32> {
+    let text = text.to_lowercase();
+    if text.contains("mostly sunny") || text.contains("mostly clear") {
+        Some(25.0)
+    } else if text.contains("partly") {
+        Some(50.0)
+    } else if text.contains("mostly cloudy") {
+        Some(75.0)
+    } else if text.contains("cloudy") || text.contains("overcast") {
+        Some(95.0)
+    } else if text.contains("sunny") || text.contains("clear") {
+        Some(5.0)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_weather_gov_wording() {
+        let cases = [
+            ("Sunny", WeatherCondition::Clear, Intensity::Moderate),
+            ("Mostly Clear", WeatherCondition::Clear, Intensity::Moderate),
+            ("Partly Cloudy", WeatherCondition::PartlyCloudy, Intensity::Moderate),
+            ("Mostly Cloudy", WeatherCondition::Cloudy, Intensity::Moderate),
+            ("Patchy Fog", WeatherCondition::Fog, Intensity::Light),
+            ("Areas Of Drizzle", WeatherCondition::Drizzle, Intensity::Moderate),
+            ("Chance Light Rain", WeatherCondition::Rain, Intensity::Light),
+            ("Heavy Rain", WeatherCondition::Rain, Intensity::Heavy),
+            ("Rain Showers Likely", WeatherCondition::Showers, Intensity::Moderate),
+            (
+                "Slight Chance Showers And Thunderstorms",
+                WeatherCondition::Thunderstorm,
+                Intensity::Moderate,
+            ),
+            ("Rain And Snow", WeatherCondition::Sleet, Intensity::Moderate),
+            ("Freezing Drizzle", WeatherCondition::Sleet, Intensity::Moderate),
+            ("Heavy Snow", WeatherCondition::Snow, Intensity::Heavy),
+            ("Haze", WeatherCondition::Haze, Intensity::Moderate),
+            ("Areas Of Smoke", WeatherCondition::Haze, Intensity::Moderate),
+            ("Breezy", WeatherCondition::Wind, Intensity::Light),
+            ("Volcanic Ash", WeatherCondition::Unknown, Intensity::Moderate),
+        ];
+        for (text, condition, intensity) in cases {
+            assert_eq!(parse_forecast(text), (condition, intensity), "{}", text);
+        }
+    }
+
+    #[test]
+    fn every_known_condition_has_a_mood() {
+        for condition in [
+            WeatherCondition::Clear,
+            WeatherCondition::PartlyCloudy,
+            WeatherCondition::Cloudy,
+            WeatherCondition::Fog,
+            WeatherCondition::Haze,
+            WeatherCondition::Drizzle,
+            WeatherCondition::Rain,
+            WeatherCondition::Showers,
+            WeatherCondition::Thunderstorm,
+            WeatherCondition::Snow,
+            WeatherCondition::Sleet,
+            WeatherCondition::Wind,
+        ] {
+            let mood = condition.mood();
+            let total = mood.happy
+                + mood.melancholic
+                + mood.hopeful
+                + mood.nostalgic
+                + mood.mysterious
+                + mood.relaxing;
+            assert!((total - 1.0).abs() < 1e-6, "{:?}", condition);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::condition::parse_forecast;
+use super::{Weather, WeatherCondition, WeatherSource};
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+// A Weather written as JSON by another program, or by hand for testing moods.
+// Without a condition, it is read from short_forecast.
+pub struct LocalFile {
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl WeatherSource for LocalFile {
+    async fn current(&self) -> Result<Weather, Box<dyn Error>> {
+        let text = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read '{}': {}", self.path.display(), e))?;
+        let mut weather: Weather = serde_json::from_str(&text)?;
+        if weather.condition == WeatherCondition::Unknown {
+            (weather.condition, weather.intensity) = parse_forecast(&weather.short_forecast);
+        }
+        Ok(weather)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_the_weather_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("weather.json");
+        fs::write(
+            &path,
+            r#"{"is_daytime": true, "temperature": 71, "probability_precipitation": 5.0, "short_forecast": "Sunny"}"#,
+        )
+        .unwrap();
+        let weather = LocalFile { path }.current().await.unwrap();
+        assert_eq!(weather.temperature, 71);
+        assert_eq!(weather.short_forecast, "Sunny");
+        assert_eq!(weather.condition, WeatherCondition::Clear);
+        assert_eq!(weather.wind_speed, None);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_files() {
+        let source = LocalFile {
+            path: PathBuf::from("/nonexistent/weather.json"),
+        };
+        assert!(source.current().await.is_err());
+    }
+}
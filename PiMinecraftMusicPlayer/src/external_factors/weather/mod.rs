@@ -0,0 +1,188 @@
+mod alerts;
+mod condition;
+mod local_file;
+mod open_meteo;
+#[cfg(test)]
+mod stub_server;
+mod trend;
+mod weather_gov;
+
+use super::factor_cache::FactorCache;
+use super::factor_provider::FactorProvider;
+use super::get_season::current_season;
+use super::get_sun::get_sun;
+use super::get_time;
+use super::mood::{MoodScores, normalize, scale_mood, sigmoid, sum_moods};
+use crate::config::{Config, WeatherBackend, get_config};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+pub use alerts::{AlertPlayback, AlertsProvider, alert_playback};
+pub use condition::{Intensity, WeatherCondition};
+use local_file::LocalFile;
+pub use trend::WeatherTrend;
+use trend::is_first_hot_day;
+use open_meteo::OpenMeteo;
+use weather_gov::WeatherGov;
+
+// Sustained wind (mph) that makes any weather feel wilder
+const WINDY_MPH: f32 = 20.0;
+
+// What every backend reports for the current hour. The extra readings are None when
+// the backend doesn't have them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Weather {
+    pub is_daytime: bool,
+    // Fahrenheit
+    pub temperature: i16,
+    // Percent, 0..100
+    pub probability_precipitation: f32,
+    pub short_forecast: String,
+    #[serde(default)]
+    pub condition: WeatherCondition,
+    #[serde(default)]
+    pub intensity: Intensity,
+    // mph
+    pub wind_speed: Option<f32>,
+    // Percent
+    pub relative_humidity: Option<f32>,
+    // Percent of the sky
+    pub cloud_cover: Option<f32>,
+    // Fahrenheit
+    pub dew_point: Option<f32>,
+    // From the upcoming hours, empty for backends without a forecast
+    #[serde(default)]
+    pub trend: WeatherTrend,
+}
+
+#[async_trait]
+pub trait WeatherSource: Send + Sync {
+    async fn current(&self) -> Result<Weather, Box<dyn Error>>;
+}
+
+// The configured backend, or why it can't be used with this config
+pub fn weather_source(config: &Config) -> Result<Box<dyn WeatherSource>, String> {
+    let weather = &config.providers.weather;
+    let location = &config.location;
+    let coordinates = location.latitude.zip(location.longitude);
+
+    match weather.backend {
+        WeatherBackend::WeatherGov => {
+            let grid = match (&location.weather_office, location.grid_x, location.grid_y) {
+                (Some(office), Some(x), Some(y)) => Some((office.clone(), x, y)),
+                _ => None,
+            };
+            if grid.is_none() && coordinates.is_none() {
+                return Err("the weather factor needs location.latitude and longitude, \
+                            or a weather_office and grid"
+                    .to_string());
+            }
+            Ok(Box::new(WeatherGov {
+                base_url: weather.weather_gov_url.clone(),
+                user_agent: weather.user_agent.clone(),
+                grid,
+                coordinates,
+            }))
+        }
+        WeatherBackend::OpenMeteo => {
+            let (latitude, longitude) = coordinates.ok_or(
+                "the open_meteo weather backend needs location.latitude and longitude",
+            )?;
+            Ok(Box::new(OpenMeteo {
+                base_url: weather.open_meteo_url.clone(),
+                user_agent: weather.user_agent.clone(),
+                latitude,
+                longitude,
+            }))
+        }
+        WeatherBackend::File => {
+            let path = weather
+                .file
+                .clone()
+                .ok_or("the file weather backend needs providers.weather.file")?;
+            Ok(Box::new(LocalFile { path }))
+        }
+    }
+}
+
+pub struct WeatherProvider;
+
+#[async_trait]
+impl FactorProvider for WeatherProvider {
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+
+    fn enabled(&self) -> bool {
+        weather_source(get_config()).is_ok()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30 * 60))
+    }
+
+    fn cache_config(&self) -> Value {
+        let config = get_config();
+        serde_json::to_value((&config.providers.weather, &config.location)).unwrap_or_default()
+    }
+
+    async fn fetch(&self) -> Result<Value, Box<dyn Error>> {
+        let mut weather = weather_source(get_config())?.current().await?;
+        let now = get_time::get_local_dt();
+        let cache = FactorCache::new(get_config().cache.dir.clone());
+        let high_today = weather.trend.high_today;
+        weather.trend.first_hot_day =
+            is_first_hot_day(&cache, now.date_naive(), current_season(&now), high_today);
+        Ok(serde_json::to_value(weather)?)
+    }
+
+    fn mood(&self, value: &Value) -> Option<MoodScores> {
+        let weather: Weather = serde_json::from_value(value.clone()).ok()?;
+
+        let mut weather_mood = MoodScores::default();
+        weather_mood.happy += sigmoid((weather.temperature as f32 - 60.0) / 10.0);
+        weather_mood.nostalgic += 1.0 - sigmoid((weather.temperature as f32 - 60.0) / 10.0);
+        let precipitation = (weather.probability_precipitation / 100.0).clamp(0.0, 1.0);
+        weather_mood.melancholic += precipitation;
+        weather_mood.relaxing += 1.0 - precipitation;
+
+        // The local sun calculation when coordinates are set, so a cached forecast
+        // doesn't keep saying day after sunset
+        let is_daytime = get_sun().map_or(weather.is_daytime, |sun| sun.is_daytime);
+        if is_daytime {
+            weather_mood.happy += 0.3;
+            weather_mood.hopeful += 0.3;
+        } else {
+            weather_mood.mysterious += 0.3;
+            weather_mood.relaxing += 0.3;
+        }
+        let condition_mood = scale_mood(weather.condition.mood(), 0.3 * weather.intensity.scale());
+        weather_mood = sum_moods(weather_mood, condition_mood);
+        if weather.condition != WeatherCondition::Wind
+            && weather.wind_speed.is_some_and(|speed| speed >= WINDY_MPH)
+        {
+            weather_mood = sum_moods(weather_mood, scale_mood(WeatherCondition::Wind.mood(), 0.2));
+        }
+
+        // Where the weather is heading, e.g. nostalgic before the rain actually starts
+        let trend = &weather.trend;
+        if trend.precipitation_in_hours.is_some() {
+            weather_mood.nostalgic += 0.3;
+            weather_mood.melancholic += 0.1;
+        }
+        if trend.clearing_up {
+            weather_mood.hopeful += 0.3;
+        }
+        if trend.temperature_dropping_fast {
+            weather_mood.mysterious += 0.2;
+        }
+        if trend.first_hot_day {
+            weather_mood.happy += 0.3;
+        }
+
+        Some(normalize(weather_mood))
+    }
+}
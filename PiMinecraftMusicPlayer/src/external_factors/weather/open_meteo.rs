@@ -0,0 +1,204 @@
+use super::trend::{HourForecast, trend};
+use super::super::http;
+use super::{Intensity, Weather, WeatherCondition, WeatherSource};
+use async_trait::async_trait;
+use chrono::{FixedOffset, NaiveDateTime, TimeZone};
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+use std::error::Error;
+
+const CURRENT_FIELDS: &str = "temperature_2m,precipitation_probability,weather_code,is_day,\
+                              wind_speed_10m,relative_humidity_2m,cloud_cover,dew_point_2m";
+const HOURLY_FIELDS: &str = "temperature_2m,precipitation_probability,weather_code";
+// Hours of forecast from the current one, for the trend
+const FORECAST_HOURS: &str = "24";
+
+// Open-Meteo's forecast API, which covers the whole world and needs no key
+pub struct OpenMeteo {
+    pub base_url: String,
+    pub user_agent: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[async_trait]
+impl WeatherSource for OpenMeteo {
+    async fn current(&self) -> Result<Weather, Box<dyn Error>> {
+        let url = format!("{}/v1/forecast", self.base_url.trim_end_matches('/'));
+        let query = [
+            ("latitude", format!("{:.4}", self.latitude)),
+            ("longitude", format!("{:.4}", self.longitude)),
+            ("current", CURRENT_FIELDS.to_string()),
+            ("hourly", HOURLY_FIELDS.to_string()),
+            ("forecast_hours", FORECAST_HOURS.to_string()),
+            ("temperature_unit", "fahrenheit".to_string()),
+            ("wind_speed_unit", "mph".to_string()),
+            ("timezone", "auto".to_string()),
+        ];
+        let body = http::client()?
+            .get(url)
+            .query(&query)
+            .header(USER_AGENT, &self.user_agent)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let v: Value = serde_json::from_str(&body)?;
+        let current = &v["current"];
+        let is_daytime = current["is_day"].as_i64().ok_or("Missing is_day")? == 1;
+        let temperature = current["temperature_2m"].as_f64().ok_or("Missing temperature_2m")?;
+        let probability_precipitation = current["precipitation_probability"]
+            .as_f64()
+            .ok_or("Missing precipitation_probability")? as f32;
+        let code = current["weather_code"].as_i64().ok_or("Missing weather_code")?;
+        let (short_forecast, condition, intensity) = weather_code(code, is_daytime);
+        let reading = |field: &str| current[field].as_f64().map(|v| v as f32);
+
+        Ok(Weather {
+            is_daytime,
+            temperature: temperature.round() as i16,
+            probability_precipitation,
+            short_forecast: short_forecast.to_string(),
+            condition,
+            intensity,
+            wind_speed: reading("wind_speed_10m"),
+            relative_humidity: reading("relative_humidity_2m"),
+            cloud_cover: reading("cloud_cover"),
+            dew_point: reading("dew_point_2m"),
+            trend: trend(&hourly_forecast(&v)),
+        })
+    }
+}
+
+// The hourly arrays zipped into hours, in the location's timezone as asked for with timezone=auto
+fn hourly_forecast(v: &Value) -> Vec<HourForecast> {
+    let Some(offset) = FixedOffset::east_opt(v["utc_offset_seconds"].as_i64().unwrap_or(0) as i32)
+    else {
+        return Vec::new();
+    };
+    let hourly = &v["hourly"];
+    let column = |field: &str| hourly[field].as_array().cloned().unwrap_or_default();
+    let (temperatures, precipitation, codes) = (
+        column("temperature_2m"),
+        column("precipitation_probability"),
+        column("weather_code"),
+    );
+
+    column("time")
+        .iter()
+        .enumerate()
+        .filter_map(|(i, time)| {
+            let time = NaiveDateTime::parse_from_str(time.as_str()?, "%Y-%m-%dT%H:%M").ok()?;
+            let code = codes.get(i)?.as_i64()?;
+            Some(HourForecast {
+                start: offset.from_local_datetime(&time).single()?,
+                temperature: temperatures.get(i)?.as_f64()? as f32,
+                probability_precipitation: precipitation.get(i)?.as_f64().unwrap_or(0.0) as f32,
+                condition: weather_code(code, true).1,
+            })
+        })
+        .collect()
+}
+
+// WMO weather codes, described in weather.gov's wording so both backends read the same
+fn weather_code(code: i64, is_daytime: bool) -> (&'static str, WeatherCondition, Intensity) {
+    use Intensity::{Heavy, Light, Moderate};
+    use WeatherCondition::*;
+    match code {
+        0 if is_daytime => ("Sunny", Clear, Moderate),
+        0 => ("Clear", Clear, Moderate),
+        1 if is_daytime => ("Mostly Sunny", Clear, Moderate),
+        1 => ("Mostly Clear", Clear, Moderate),
+        2 => ("Partly Cloudy", PartlyCloudy, Moderate),
+        3 => ("Cloudy", Cloudy, Moderate),
+        45 | 48 => ("Fog", Fog, Moderate),
+        51 => ("Light Drizzle", Drizzle, Light),
+        53 => ("Drizzle", Drizzle, Moderate),
+        55 => ("Heavy Drizzle", Drizzle, Heavy),
+        56 => ("Light Freezing Drizzle", Sleet, Light),
+        57 => ("Freezing Drizzle", Sleet, Heavy),
+        61 => ("Light Rain", Rain, Light),
+        63 => ("Rain", Rain, Moderate),
+        65 => ("Heavy Rain", Rain, Heavy),
+        66 => ("Light Freezing Rain", Sleet, Light),
+        67 => ("Freezing Rain", Sleet, Heavy),
+        71 => ("Light Snow", Snow, Light),
+        73 => ("Snow", Snow, Moderate),
+        75 => ("Heavy Snow", Snow, Heavy),
+        77 => ("Snow Grains", Snow, Light),
+        80 => ("Light Rain Showers", Showers, Light),
+        81 => ("Rain Showers", Showers, Moderate),
+        82 => ("Heavy Rain Showers", Showers, Heavy),
+        85 => ("Snow Showers", Snow, Light),
+        86 => ("Heavy Snow Showers", Snow, Heavy),
+        95 => ("Thunderstorms", Thunderstorm, Moderate),
+        96 | 99 => ("Thunderstorms With Hail", Thunderstorm, Heavy),
+        _ => ("Unknown", Unknown, Moderate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub_server::StubServer;
+    use super::*;
+
+    const FORECAST: &str = include_str!("../../../testdata/open_meteo_forecast.json");
+
+    #[tokio::test]
+    async fn reads_current_conditions() {
+        let server = StubServer::start();
+        server.route("/v1/forecast", 200, FORECAST);
+
+        let source = OpenMeteo {
+            base_url: server.url(),
+            user_agent: "test".to_string(),
+            latitude: 32.88,
+            longitude: -117.23,
+        };
+        let weather = source.current().await.unwrap();
+        assert_eq!(weather.temperature, 62);
+        assert!(!weather.is_daytime);
+        assert_eq!(weather.probability_precipitation, 35.0);
+        assert_eq!(weather.short_forecast, "Cloudy");
+        assert_eq!(weather.condition, WeatherCondition::Cloudy);
+        assert_eq!(weather.wind_speed, Some(12.4));
+        assert_eq!(weather.relative_humidity, Some(84.0));
+        assert_eq!(weather.cloud_cover, Some(100.0));
+        assert_eq!(weather.dew_point, Some(56.9));
+        assert!(weather.trend.clearing_up);
+        assert_eq!(weather.trend.high_today, Some(64.0));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("latitude=32.8800"));
+        assert!(requests[0].contains("longitude=-117.2300"));
+        assert!(requests[0].contains("temperature_unit=fahrenheit"));
+        assert!(requests[0].contains("wind_speed_unit=mph"));
+        assert!(requests[0].contains("forecast_hours=24"));
+    }
+
+    #[tokio::test]
+    async fn fails_on_server_errors() {
+        let server = StubServer::start();
+        server.route("/v1/forecast", 500, r#"{"error": true}"#);
+
+        let source = OpenMeteo {
+            base_url: server.url(),
+            user_agent: "test".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+        };
+        assert!(source.current().await.is_err());
+    }
+
+    #[test]
+    fn weather_codes_map_to_conditions() {
+        assert_eq!(weather_code(0, true).0, "Sunny");
+        assert_eq!(weather_code(0, false).0, "Clear");
+        assert_eq!(weather_code(65, true), ("Heavy Rain", WeatherCondition::Rain, Intensity::Heavy));
+        assert_eq!(weather_code(56, true).1, WeatherCondition::Sleet);
+        assert_eq!(weather_code(99, false).1, WeatherCondition::Thunderstorm);
+    }
+}
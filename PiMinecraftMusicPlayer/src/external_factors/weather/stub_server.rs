@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Routes = Arc<Mutex<HashMap<String, (u16, String)>>>;
+
+// A local HTTP server answering GETs with canned bodies, routed by path without the query.
+// Runs on its own thread until the test process exits.
+pub struct StubServer {
+    addr: SocketAddr,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind stub server");
+        let server = StubServer {
+            addr: listener.local_addr().expect("stub server address"),
+            routes: Arc::default(),
+            requests: Arc::default(),
+        };
+
+        let routes = server.routes.clone();
+        let requests = server.requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                respond(stream, &routes, &requests);
+            }
+        });
+        server
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn route(&self, path: &str, status: u16, body: &str) {
+        let mut routes = self.routes.lock().unwrap();
+        routes.insert(path.to_string(), (status, body.to_string()));
+    }
+
+    // Paths and queries requested so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn respond(mut stream: TcpStream, routes: &Routes, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Headers only, GETs have no body
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line.trim() != "" {
+        line.clear();
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let path = target.split('?').next().unwrap_or("/").to_string();
+    requests.lock().unwrap().push(target);
+
+    let (status, body) = routes
+        .lock()
+        .unwrap()
+        .get(&path)
+        .cloned()
+        .unwrap_or((404, r#"{"error": "no route"}"#.to_string()));
+    let response = format!(
+        "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
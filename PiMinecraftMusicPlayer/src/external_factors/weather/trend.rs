@@ -0,0 +1,261 @@
+use super::super::factor_cache::FactorCache;
+use super::super::get_season::Season;
+use super::WeatherCondition;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// How far ahead "arriving" and "clearing up" look
+const WINDOW_HOURS: usize = 3;
+// Percent chance from which an hour counts as wet
+const WET_PROBABILITY: f32 = 50.0;
+// Fahrenheit drop over the window that counts as fast
+const FAST_DROP: f32 = 8.0;
+// Fahrenheit high that makes a hot day
+const HOT_DAY: f32 = 85.0;
+// A hot day this long after the last one starts a new hot spell
+const HOT_SPELL_GAP_DAYS: i64 = 90;
+const HOT_DAYS_CACHE: &str = "weather_hot_days";
+
+// One hour of a forecast, the current hour first
+pub struct HourForecast {
+    pub start: DateTime<FixedOffset>,
+    pub temperature: f32,
+    pub probability_precipitation: f32,
+    pub condition: WeatherCondition,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherTrend {
+    // Hours until rain, snow or storms start, when it is dry now and they are due within the window
+    pub precipitation_in_hours: Option<u32>,
+    // Wet, cloudy or foggy now and clear by the end of the window
+    pub clearing_up: bool,
+    // Fahrenheit change over the window
+    pub temperature_change: Option<f32>,
+    pub temperature_dropping_fast: bool,
+    // Highest temperature for the rest of today
+    pub high_today: Option<f32>,
+    pub first_hot_day: bool,
+}
+
+// Days the hot day check has seen, kept in the factor cache between runs
+#[derive(Debug, Serialize, Deserialize)]
+struct HotDays {
+    first: NaiveDate,
+    last: NaiveDate,
+    season: Season,
+}
+
+impl HourForecast {
+    fn is_wet(&self) -> bool {
+        self.probability_precipitation >= WET_PROBABILITY
+            || matches!(
+                self.condition,
+                WeatherCondition::Drizzle
+                    | WeatherCondition::Rain
+                    | WeatherCondition::Showers
+                    | WeatherCondition::Thunderstorm
+                    | WeatherCondition::Snow
+                    | WeatherCondition::Sleet
+            )
+    }
+
+    fn is_clear(&self) -> bool {
+        !self.is_wet()
+            && matches!(self.condition, WeatherCondition::Clear | WeatherCondition::PartlyCloudy)
+    }
+}
+
+// Everything but first_hot_day, which needs the days seen by earlier runs
+pub fn trend(hours: &[HourForecast]) -> WeatherTrend {
+    let Some(now) = hours.first() else {
+        return WeatherTrend::default();
+    };
+    let window = &hours[1..hours.len().min(WINDOW_HOURS + 1)];
+    let end = window.last();
+
+    let precipitation_in_hours = if now.is_wet() {
+        None
+    } else {
+        window.iter().position(|h| h.is_wet()).map(|i| i as u32 + 1)
+    };
+    let gloomy = now.is_wet()
+        || matches!(now.condition, WeatherCondition::Cloudy | WeatherCondition::Fog);
+    let temperature_change = end.map(|h| h.temperature - now.temperature);
+    let high_today = hours
+        .iter()
+        .filter(|h| h.start.date_naive() == now.start.date_naive())
+        .map(|h| h.temperature)
+        .reduce(f32::max);
+
+    WeatherTrend {
+        precipitation_in_hours,
+        clearing_up: gloomy && end.is_some_and(|h| h.is_clear()),
+        temperature_change,
+        temperature_dropping_fast: temperature_change.is_some_and(|c| c <= -FAST_DROP),
+        high_today,
+        first_hot_day: false,
+    }
+}
+
+// Whether today is the first hot day of the season, remembering hot days in the cache.
+// Every check on that day says yes, not just the first one.
+pub fn is_first_hot_day(
+    cache: &FactorCache,
+    today: NaiveDate,
+    season: Season,
+    high_today: Option<f32>,
+) -> bool {
+    if high_today.is_none_or(|high| high < HOT_DAY) {
+        return false;
+    }
+
+    let seen: Option<HotDays> = cache
+        .get(HOT_DAYS_CACHE, &Value::Null)
+        .and_then(|entry| serde_json::from_value(entry.value).ok());
+    let first = match seen {
+        Some(seen)
+            if seen.season == season && (today - seen.last).num_days() <= HOT_SPELL_GAP_DAYS =>
+        {
+            seen.first
+        }
+        _ => today,
+    };
+    if let Ok(value) = serde_json::to_value(HotDays {
+        first,
+        last: today,
+        season,
+    }) {
+        cache.put(HOT_DAYS_CACHE, &Value::Null, &value);
+    }
+    first == today
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn hours(forecast: &[(f32, f32, WeatherCondition)]) -> Vec<HourForecast> {
+        let start = DateTime::parse_from_rfc3339("2026-10-18T15:00:00-07:00").unwrap();
+        forecast
+            .iter()
+            .enumerate()
+            .map(|(i, &(temperature, probability_precipitation, condition))| HourForecast {
+                start: start + Duration::hours(i as i64),
+                temperature,
+                probability_precipitation,
+                condition,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rain_arriving_within_the_window() {
+        let forecast = hours(&[
+            (64.0, 10.0, WeatherCondition::Cloudy),
+            (63.0, 20.0, WeatherCondition::Cloudy),
+            (61.0, 60.0, WeatherCondition::Rain),
+            (60.0, 70.0, WeatherCondition::Rain),
+        ]);
+        let trend = trend(&forecast);
+        assert_eq!(trend.precipitation_in_hours, Some(2));
+        assert!(!trend.clearing_up);
+        assert_eq!(trend.temperature_change, Some(-4.0));
+        assert!(!trend.temperature_dropping_fast);
+    }
+
+    #[test]
+    fn rain_beyond_the_window_is_not_arriving() {
+        let forecast = hours(&[
+            (64.0, 10.0, WeatherCondition::Clear),
+            (64.0, 10.0, WeatherCondition::Clear),
+            (64.0, 10.0, WeatherCondition::Clear),
+            (64.0, 20.0, WeatherCondition::PartlyCloudy),
+            (60.0, 80.0, WeatherCondition::Rain),
+        ]);
+        assert_eq!(trend(&forecast).precipitation_in_hours, None);
+    }
+
+    #[test]
+    fn clearing_up_after_rain() {
+        let forecast = hours(&[
+            (55.0, 70.0, WeatherCondition::Rain),
+            (56.0, 40.0, WeatherCondition::Showers),
+            (58.0, 20.0, WeatherCondition::Cloudy),
+            (60.0, 10.0, WeatherCondition::PartlyCloudy),
+        ]);
+        let trend = trend(&forecast);
+        assert!(trend.clearing_up);
+        assert_eq!(trend.precipitation_in_hours, None);
+    }
+
+    #[test]
+    fn fast_drops_and_todays_high() {
+        let forecast = hours(&[
+            (88.0, 0.0, WeatherCondition::Clear),
+            (91.0, 0.0, WeatherCondition::Clear),
+            (84.0, 40.0, WeatherCondition::Thunderstorm),
+            (78.0, 60.0, WeatherCondition::Thunderstorm),
+        ]);
+        let trend = trend(&forecast);
+        assert!(trend.temperature_dropping_fast);
+        assert_eq!(trend.high_today, Some(91.0));
+        assert_eq!(trend.precipitation_in_hours, Some(2));
+    }
+
+    #[test]
+    fn tomorrow_does_not_count_for_todays_high() {
+        let start = DateTime::parse_from_rfc3339("2026-10-18T23:00:00-07:00").unwrap();
+        let forecast: Vec<HourForecast> = [70.0, 95.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &temperature)| HourForecast {
+                start: start + Duration::hours(i as i64),
+                temperature,
+                probability_precipitation: 0.0,
+                condition: WeatherCondition::Clear,
+            })
+            .collect();
+        assert_eq!(trend(&forecast).high_today, Some(70.0));
+    }
+
+    #[test]
+    fn first_hot_day_of_a_spell() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FactorCache::new(dir.path().to_path_buf());
+        let day = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        let hot = Some(91.0);
+
+        let checks = [
+            // Too cool to count, and not remembered
+            (day(6, 20), Season::Spring, Some(80.0), false),
+            (day(6, 22), Season::Summer, hot, true),
+            // Every check on the same day
+            (day(6, 22), Season::Summer, hot, true),
+            (day(6, 23), Season::Summer, hot, false),
+            (day(9, 1), Season::Summer, hot, false),
+            // The equinox starts a new season
+            (day(9, 23), Season::Fall, hot, true),
+            (day(9, 24), Season::Fall, None, false),
+        ];
+        for (today, season, high, expected) in checks {
+            assert_eq!(is_first_hot_day(&cache, today, season, high), expected, "{}", today);
+        }
+
+        // More than the gap since the last hot day starts a new spell, even in the same season
+        let after_gap = day(9, 23) + chrono::Days::new(HOT_SPELL_GAP_DAYS as u64 + 1);
+        assert!(is_first_hot_day(&cache, after_gap, Season::Fall, hot));
+    }
+
+    #[test]
+    fn a_single_hour_has_no_trend() {
+        let forecast = hours(&[(64.0, 10.0, WeatherCondition::Clear)]);
+        let trend = trend(&forecast);
+        assert_eq!(trend.temperature_change, None);
+        assert_eq!(trend.precipitation_in_hours, None);
+        assert_eq!(trend.high_today, Some(64.0));
+    }
+}
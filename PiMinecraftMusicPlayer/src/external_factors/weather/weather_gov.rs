@@ -0,0 +1,209 @@
+use super::condition::{parse_forecast, sky_cover_estimate};
+use super::trend::{HourForecast, trend};
+use super::super::http;
+use super::{Weather, WeatherSource};
+use async_trait::async_trait;
+use chrono::DateTime;
+use reqwest::Client;
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+use std::error::Error;
+
+// api.weather.gov's hourly forecast, for a known grid or the grid covering the coordinates
+pub struct WeatherGov {
+    pub base_url: String,
+    pub user_agent: String,
+    // Forecast office and grid x, y
+    pub grid: Option<(String, u16, u16)>,
+    pub coordinates: Option<(f64, f64)>,
+}
+
+impl WeatherGov {
+    async fn get_json(&self, client: &Client, url: &str) -> Result<Value, Box<dyn Error>> {
+        let body = client
+            .get(url)
+            .header(USER_AGENT, &self.user_agent)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    // The configured grid if there is one, otherwise weather.gov's lookup for the coordinates
+    async fn forecast_url(&self, client: &Client) -> Result<String, Box<dyn Error>> {
+        let base_url = self.base_url.trim_end_matches('/');
+        if let Some((office, x, y)) = &self.grid {
+            return Ok(format!("{}/gridpoints/{}/{},{}/forecast/hourly", base_url, office, x, y));
+        }
+
+        let (lat, lon) = self.coordinates.ok_or("Missing latitude and longitude")?;
+        let url = format!("{}/points/{:.4},{:.4}", base_url, lat, lon);
+        let v = self.get_json(client, &url).await?;
+        let url = v["properties"]["forecastHourly"]
+            .as_str()
+            .ok_or("Missing forecastHourly, the location may be outside weather.gov coverage")?;
+        Ok(url.to_string())
+    }
+}
+
+#[async_trait]
+impl WeatherSource for WeatherGov {
+    async fn current(&self) -> Result<Weather, Box<dyn Error>> {
+        let client = http::client()?;
+        let url = self.forecast_url(&client).await?;
+        let v = self.get_json(&client, &url).await?;
+
+        let period = &v["properties"]["periods"][0];
+        let is_daytime = period["isDaytime"].as_bool().ok_or("Missing daytime");
+        let temperature = period["temperature"].as_i64().ok_or("Missing temp field")? as i16;
+        // Null when there is no chance at all
+        let probability_precipitation = period["probabilityOfPrecipitation"]["value"]
+            .as_f64()
+            .unwrap_or(0.0) as f32;
+        let short_forecast = period["shortForecast"]
+            .as_str()
+            .ok_or("Missing shortForecast")?
+            .to_string();
+        let (condition, intensity) = parse_forecast(&short_forecast);
+        let hours: Vec<HourForecast> = v["properties"]["periods"]
+            .as_array()
+            .map(|periods| periods.iter().filter_map(hour_forecast).collect())
+            .unwrap_or_default();
+        let weather = Weather {
+            is_daytime: is_daytime?,
+            temperature,
+            probability_precipitation,
+            condition,
+            intensity,
+            wind_speed: period["windSpeed"].as_str().and_then(parse_wind_speed),
+            relative_humidity: period["relativeHumidity"]["value"].as_f64().map(|h| h as f32),
+            cloud_cover: sky_cover_estimate(&short_forecast),
+            dew_point: fahrenheit(&period["dewpoint"]),
+            short_forecast,
+            trend: trend(&hours),
+        };
+
+        Ok(weather)
+    }
+}
+
+fn hour_forecast(period: &Value) -> Option<HourForecast> {
+    let short_forecast = period["shortForecast"].as_str().unwrap_or_default();
+    Some(HourForecast {
+        start: DateTime::parse_from_rfc3339(period["startTime"].as_str()?).ok()?,
+        temperature: period["temperature"].as_f64()? as f32,
+        // Null when there is no chance at all
+        probability_precipitation: period["probabilityOfPrecipitation"]["value"]
+            .as_f64()
+            .unwrap_or(0.0) as f32,
+        condition: parse_forecast(short_forecast).0,
+    })
+}
+
+// "10 mph" or a range like "10 to 15 mph", which counts as its top speed
+fn parse_wind_speed(text: &str) -> Option<f32> {
+    let top = text
+        .split_whitespace()
+        .filter_map(|word| word.parse::<f32>().ok())
+        .reduce(f32::max)?;
+    Some(if text.contains("km/h") { top / 1.609 } else { top })
+}
+
+// A { unitCode, value } quantity, which weather.gov gives in Celsius for dew points
+fn fahrenheit(quantity: &Value) -> Option<f32> {
+    let value = quantity["value"].as_f64()? as f32;
+    match quantity["unitCode"].as_str() {
+        Some("wmoUnit:degF") => Some(value),
+        _ => Some(value * 9.0 / 5.0 + 32.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::WeatherCondition;
+    use super::super::stub_server::StubServer;
+    use super::*;
+
+    const POINTS: &str = include_str!("../../../testdata/weather_gov_points.json");
+    const HOURLY: &str = include_str!("../../../testdata/weather_gov_hourly.json");
+
+    fn weather_gov(server: &StubServer, grid: Option<(String, u16, u16)>) -> WeatherGov {
+        WeatherGov {
+            base_url: server.url(),
+            user_agent: "test".to_string(),
+            grid,
+            coordinates: Some((32.8801, -117.2340)),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_the_grid_from_points() {
+        let server = StubServer::start();
+        let forecast = format!("{}/gridpoints/SGX/55,21/forecast/hourly", server.url());
+        server.route("/points/32.8801,-117.2340", 200, &POINTS.replace("{forecast}", &forecast));
+        server.route("/gridpoints/SGX/55,21/forecast/hourly", 200, HOURLY);
+
+        let weather = weather_gov(&server, None).current().await.unwrap();
+        assert_eq!(weather.temperature, 64);
+        assert!(weather.is_daytime);
+        assert_eq!(weather.probability_precipitation, 20.0);
+        assert_eq!(weather.short_forecast, "Partly Cloudy");
+        assert_eq!(weather.condition, WeatherCondition::PartlyCloudy);
+        assert_eq!(weather.wind_speed, Some(5.0));
+        assert_eq!(weather.relative_humidity, Some(78.0));
+        assert_eq!(weather.cloud_cover, Some(50.0));
+        assert!((weather.dew_point.unwrap() - 54.0).abs() < 0.01);
+        // "Chance Light Rain" three hours out
+        assert_eq!(weather.trend.precipitation_in_hours, Some(3));
+        assert_eq!(weather.trend.temperature_change, Some(-4.0));
+        assert_eq!(weather.trend.high_today, Some(64.0));
+        assert_eq!(
+            server.requests(),
+            ["/points/32.8801,-117.2340", "/gridpoints/SGX/55,21/forecast/hourly"]
+        );
+    }
+
+    #[tokio::test]
+    async fn uses_a_configured_grid_above_127() {
+        let server = StubServer::start();
+        server.route("/gridpoints/BOX/200,150/forecast/hourly", 200, HOURLY);
+
+        let grid = Some(("BOX".to_string(), 200, 150));
+        let weather = weather_gov(&server, grid).current().await.unwrap();
+        assert_eq!(weather.temperature, 64);
+        assert_eq!(server.requests(), ["/gridpoints/BOX/200,150/forecast/hourly"]);
+    }
+
+    #[tokio::test]
+    async fn a_dry_hour_without_a_chance_of_rain_is_zero() {
+        let server = StubServer::start();
+        let dry = HOURLY.replacen(
+            "\"unitCode\": \"wmoUnit:percent\",\n                    \"value\": 20",
+            "\"unitCode\": \"wmoUnit:percent\",\n                    \"value\": null",
+            1,
+        );
+        assert_ne!(dry, HOURLY);
+        server.route("/gridpoints/SGX/55,21/forecast/hourly", 200, &dry);
+
+        let grid = Some(("SGX".to_string(), 55, 21));
+        let weather = weather_gov(&server, grid).current().await.unwrap();
+        assert_eq!(weather.probability_precipitation, 0.0);
+    }
+
+    #[tokio::test]
+    async fn reports_locations_outside_coverage() {
+        let server = StubServer::start();
+        server.route("/points/32.8801,-117.2340", 404, r#"{"title": "Data Unavailable"}"#);
+
+        assert!(weather_gov(&server, None).current().await.is_err());
+    }
+
+    #[test]
+    fn wind_ranges_count_their_top_speed() {
+        assert_eq!(parse_wind_speed("5 mph"), Some(5.0));
+        assert_eq!(parse_wind_speed("10 to 15 mph"), Some(15.0));
+        assert_eq!(parse_wind_speed(""), None);
+    }
+}
@@ -1,21 +1,25 @@
-use player::play_song;
-use scheduler::scheduler::schedule_cron;
-use std::env;
+use clap::Parser;
+use cli::{Cli, Command};
+use std::{env, process};
 
+mod cli;
+mod config;
 mod external_factors;
 mod player;
 mod scheduler;
 
 #[tokio::main]
 async fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mode = args[1].parse::<i32>().unwrap();
+    let args = cli::translate_legacy_args(env::args().collect());
+    let cli = Cli::parse_from(args);
 
-    player::song_picker::load_song_data();
-
-    if mode == 0 {
-        schedule_cron();
-    } else {
-        play_song().await;
+    // `config check` reports problems itself instead of refusing to start
+    if !matches!(cli.command, Command::Config { .. })
+        && let Err(e) = config::init_config(&cli.config)
+    {
+        eprintln!("{}", e);
+        process::exit(cli::EXIT_CONFIG);
     }
+
+    process::exit(cli::run(cli.command, &cli.config).await);
 }
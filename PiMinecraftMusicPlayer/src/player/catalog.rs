@@ -0,0 +1,436 @@
+use crate::config::get_config;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::error::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+const AUDIO_EXTENSIONS: [&str; 4] = ["mp3", "ogg", "oga", "flac"];
+const MAX_PLAUSIBLE_DURATION: Duration = Duration::from_secs(30 * 60);
+
+// One entry of songs_ranking.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct Song {
+    pub track: String,
+    // Optional path relative to the album directory, used when the title alone is ambiguous
+    #[serde(default)]
+    pub file: Option<String>,
+    pub happy: f32,
+    pub melancholic: f32,
+    pub hopeful: f32,
+    pub nostalgic: f32,
+    pub mysterious: f32,
+    pub relaxing: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub title: String,
+    pub album: String,
+    pub track_number: Option<u32>,
+    pub duration: Option<Duration>,
+    pub rating: Song,
+}
+
+#[derive(Debug, Default)]
+pub struct Catalog {
+    pub tracks: Vec<Track>,
+    pub unmatched_files: Vec<PathBuf>,
+    pub unmatched_ratings: Vec<String>,
+    // Rated tracks without a `file` whose titles normalize to the same thing, so a title
+    // alone can't tell which file each rating belongs to
+    pub ambiguous_ratings: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct TrackTags {
+    title: Option<String>,
+    album: Option<String>,
+    track_number: Option<u32>,
+    duration: Option<Duration>,
+}
+
+impl Catalog {
+    // Albums in catalog order, the first one is treated as the first volume by the picker
+    pub fn albums(&self) -> Vec<&str> {
+        let mut albums: Vec<&str> = Vec::new();
+        for track in &self.tracks {
+            if !albums.contains(&track.album.as_str()) {
+                albums.push(&track.album);
+            }
+        }
+        albums
+    }
+
+    // Longest known track, used to keep scheduled plays from overlapping. Durations over
+    // MAX_PLAUSIBLE_DURATION come from broken tags and are ignored.
+    pub fn longest_duration(&self) -> Option<Duration> {
+        self.tracks
+            .iter()
+            .filter_map(|t| t.duration)
+            .filter(|d| *d <= MAX_PLAUSIBLE_DURATION)
+            .max()
+    }
+
+    pub fn report(&self) {
+        println!(
+            "Catalog: {} tracks in {} albums, longest track {}s",
+            self.tracks.len(),
+            self.albums().len(),
+            self.longest_duration().map_or(0, |d| d.as_secs())
+        );
+
+        for path in &self.unmatched_files {
+            eprintln!("Warning: no rating for file '{}'", path.display());
+        }
+        for track in &self.unmatched_ratings {
+            eprintln!("Warning: no file found for rated track '{}'", track);
+        }
+        for track in &self.ambiguous_ratings {
+            eprintln!(
+                "Warning: rated track '{}' shares its title with another, add `file` to match it",
+                track
+            );
+        }
+    }
+}
+
+pub fn load_catalog() -> Result<Catalog, Box<dyn Error>> {
+    let paths = &get_config().paths;
+    let album_path = paths.album.as_ref().ok_or("paths.album is not set")?;
+    let song_json_path = paths.song_json.as_ref().ok_or("paths.song_json is not set")?;
+
+    let ratings = load_ratings(song_json_path)?;
+    build_catalog(album_path, ratings)
+}
+
+pub fn load_ratings(path: &Path) -> Result<Vec<Song>, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open ratings file '{}': {}", path.display(), e))?;
+    let reader = BufReader::new(file);
+    let songs: Vec<Song> = serde_json::from_reader(reader)
+        .map_err(|e| format!("Failed to parse ratings file '{}': {}", path.display(), e))?;
+    Ok(songs)
+}
+
+pub fn build_catalog(album_path: &Path, ratings: Vec<Song>) -> Result<Catalog, Box<dyn Error>> {
+    let mut files = Vec::new();
+    scan_dir(album_path, &mut files)
+        .map_err(|e| format!("Failed to scan album directory '{}': {}", album_path.display(), e))?;
+
+    let mut by_file: HashMap<String, usize> = HashMap::new();
+    let mut by_title: HashMap<String, usize> = HashMap::new();
+    let mut ambiguous_titles: HashSet<String> = HashSet::new();
+    for (i, rating) in ratings.iter().enumerate() {
+        match &rating.file {
+            Some(file) => {
+                by_file.insert(normalize_path(file), i);
+            }
+            None => {
+                let title = normalize_title(&rating.track);
+                if by_title.insert(title.clone(), i).is_some() {
+                    ambiguous_titles.insert(title);
+                }
+            }
+        }
+    }
+    by_title.retain(|title, _| !ambiguous_titles.contains(title));
+
+    let mut catalog = Catalog::default();
+    let mut used = vec![false; ratings.len()];
+
+    for path in files {
+        let relative_path = path
+            .strip_prefix(album_path)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let tags = read_tags(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: failed to read tags from '{}': {}", path.display(), e);
+            TrackTags::default()
+        });
+
+        let stem_title = path
+            .file_stem()
+            .map(|s| strip_track_number(&s.to_string_lossy()).to_string())
+            .unwrap_or_default();
+        let title = tags.title.clone().unwrap_or(stem_title.clone());
+
+        let rating_idx = by_file
+            .get(&normalize_path(&relative_path))
+            .or_else(|| by_title.get(&normalize_title(&title)))
+            .or_else(|| by_title.get(&normalize_title(&stem_title)));
+
+        let Some(&rating_idx) = rating_idx else {
+            catalog.unmatched_files.push(path);
+            continue;
+        };
+        used[rating_idx] = true;
+
+        let album = tags.album.unwrap_or_else(|| {
+            path.parent()
+                .and_then(|p| p.file_name())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        let track_number = tags
+            .track_number
+            .or_else(|| leading_track_number(&path.file_stem()?.to_string_lossy()));
+
+        catalog.tracks.push(Track {
+            path,
+            relative_path,
+            title,
+            album,
+            track_number,
+            duration: tags.duration,
+            rating: ratings[rating_idx].clone(),
+        });
+    }
+
+    catalog.tracks.sort_by(|a, b| {
+        (&a.album, a.track_number, &a.relative_path).cmp(&(&b.album, b.track_number, &b.relative_path))
+    });
+
+    for (i, rating) in ratings.iter().enumerate() {
+        if used[i] {
+            continue;
+        }
+        if rating.file.is_none() && ambiguous_titles.contains(&normalize_title(&rating.track)) {
+            catalog.ambiguous_ratings.push(rating.track.clone());
+        } else {
+            catalog.unmatched_ratings.push(rating.track.clone());
+        }
+    }
+
+    Ok(catalog)
+}
+
+fn scan_dir(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            scan_dir(&path, files)?;
+        } else if is_audio_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn read_tags(path: &Path) -> Result<TrackTags, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension() {
+        hint.with_extension(&ext.to_string_lossy());
+    }
+
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    let mut tags = TrackTags::default();
+
+    // ID3 tags come from the probe, Vorbis comments from the container itself
+    if let Some(rev) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        apply_tags(&mut tags, rev);
+    }
+    if let Some(rev) = probed.format.metadata().current() {
+        apply_tags(&mut tags, rev);
+    }
+
+    let format = &mut probed.format;
+    if let Some(track) = format.default_track() {
+        let params = &track.codec_params;
+        let track_id = track.id;
+
+        if let (Some(n_frames), Some(time_base)) = (params.n_frames, params.time_base) {
+            let time = time_base.calc_time(n_frames);
+            tags.duration = Some(Duration::from_secs_f64(time.seconds as f64 + time.frac));
+        } else if let Some(time_base) = params.time_base {
+            // No frame count in the header, add up the packets instead
+            let mut n_frames = 0;
+            while let Ok(packet) = format.next_packet() {
+                if packet.track_id() == track_id {
+                    n_frames += packet.dur;
+                }
+            }
+            let time = time_base.calc_time(n_frames);
+            tags.duration = Some(Duration::from_secs_f64(time.seconds as f64 + time.frac));
+        }
+    }
+
+    Ok(tags)
+}
+
+fn apply_tags(tags: &mut TrackTags, rev: &MetadataRevision) {
+    for tag in rev.tags() {
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => tags.title = Some(tag.value.to_string()),
+            Some(StandardTagKey::Album) => tags.album = Some(tag.value.to_string()),
+            Some(StandardTagKey::TrackNumber) => {
+                // Track numbers are often stored as "3/24"
+                let value = tag.value.to_string();
+                let number = value.split('/').next().unwrap_or("").trim();
+                tags.track_number = number.parse().ok();
+            }
+            _ => {}
+        }
+    }
+}
+
+// "01 - Key" and "01. Ki" both become the bare title
+fn strip_track_number(stem: &str) -> &str {
+    let rest = stem.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == stem.len() {
+        return stem;
+    }
+    let title = rest.trim_start_matches(['.', '-', '_', ' ']);
+    if title.len() == rest.len() || title.is_empty() {
+        return stem;
+    }
+    title
+}
+
+fn leading_track_number(stem: &str) -> Option<u32> {
+    let digits: String = stem.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(track: &str, file: Option<&str>, happy: f32) -> Song {
+        Song {
+            track: track.to_string(),
+            file: file.map(|f| f.to_string()),
+            happy,
+            melancholic: 0.0,
+            hopeful: 0.0,
+            nostalgic: 0.0,
+            mysterious: 0.0,
+            relaxing: 0.0,
+        }
+    }
+
+    fn happy_for(catalog: &Catalog, relative_path: &str) -> Option<f32> {
+        catalog
+            .tracks
+            .iter()
+            .find(|t| t.relative_path == relative_path)
+            .map(|t| t.rating.happy)
+    }
+
+    #[test]
+    fn strips_track_numbers() {
+        let cases = [
+            ("01 - Key", "Key"),
+            ("01. Ki", "Ki"),
+            ("3_Subwoofer Lullaby", "Subwoofer Lullaby"),
+            // Nothing after the number, or no separator, is left alone
+            ("1990", "1990"),
+            ("13 ", "13 "),
+            ("2nd Wind", "2nd Wind"),
+            ("Sweden", "Sweden"),
+        ];
+        for (stem, title) in cases {
+            assert_eq!(strip_track_number(stem), title, "{}", stem);
+        }
+        assert_eq!(leading_track_number("07 - Mice on Venus"), Some(7));
+        assert_eq!(leading_track_number("Mice on Venus"), None);
+    }
+
+    #[test]
+    fn normalizes_titles_and_paths() {
+        assert_eq!(normalize_title("Mice on Venus"), "miceonvenus");
+        assert_eq!(normalize_title("Dry Hands!"), normalize_title("dry-hands"));
+        assert_eq!(normalize_path(".\\Volume Alpha\\01 Key.mp3"), "volume alpha/01 key.mp3");
+        assert_eq!(normalize_path("./Volume Beta/Ki.MP3"), "volume beta/ki.mp3");
+    }
+
+    #[test]
+    fn joins_ratings_by_file_then_title() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "Volume Alpha/01 - Key.mp3",
+            "Volume Alpha/02. Door.mp3",
+            "Volume Alpha/03 - Sweden.mp3",
+            "Volume Beta/03 - Sweden.mp3",
+            "Volume Beta/04 - Minecraft.mp3",
+            "Volume Beta/05 - Unrated.mp3",
+            "Volume Beta/notes.txt",
+        ] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            // Not real audio, so every title comes from the file name
+            fs::write(path, b"").unwrap();
+        }
+
+        let ratings = vec![
+            rating("Key", None, 0.1),
+            rating("door", None, 0.2),
+            // Two Swedens told apart by file
+            rating("Sweden", Some("Volume Alpha/03 - Sweden.mp3"), 0.3),
+            rating("Sweden", Some("volume beta\\03 - sweden.mp3"), 0.4),
+            // Same normalized title twice without a file, neither can be trusted
+            rating("Minecraft", None, 0.5),
+            rating("minecraft!", None, 0.6),
+            rating("Not in the library", None, 0.7),
+        ];
+        let catalog = build_catalog(dir.path(), ratings).unwrap();
+
+        assert_eq!(happy_for(&catalog, "Volume Alpha/01 - Key.mp3"), Some(0.1));
+        assert_eq!(happy_for(&catalog, "Volume Alpha/02. Door.mp3"), Some(0.2));
+        assert_eq!(happy_for(&catalog, "Volume Alpha/03 - Sweden.mp3"), Some(0.3));
+        assert_eq!(happy_for(&catalog, "Volume Beta/03 - Sweden.mp3"), Some(0.4));
+        assert_eq!(happy_for(&catalog, "Volume Beta/04 - Minecraft.mp3"), None);
+        assert_eq!(catalog.tracks.len(), 4);
+        assert_eq!(catalog.albums(), ["Volume Alpha", "Volume Beta"]);
+        assert_eq!(catalog.tracks[0].track_number, Some(1));
+
+        let unmatched: Vec<PathBuf> = ["Volume Beta/04 - Minecraft.mp3", "Volume Beta/05 - Unrated.mp3"]
+            .iter()
+            .map(|f| dir.path().join(f))
+            .collect();
+        assert_eq!(catalog.unmatched_files, unmatched);
+        assert_eq!(catalog.ambiguous_ratings, ["Minecraft", "minecraft!"]);
+        assert_eq!(catalog.unmatched_ratings, ["Not in the library"]);
+    }
+}
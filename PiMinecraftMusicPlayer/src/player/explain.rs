@@ -0,0 +1,141 @@
+use crate::external_factors::ExternalFactors;
+use crate::external_factors::mood::MoodScores;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Everything that went into one pick, printed by `explain` and appended to the
+// explanation log on every real play
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Explanation {
+    pub timestamp: String,
+    pub factors: Vec<FactorExplanation>,
+    pub combined_mood: MoodScores,
+    pub first_volume: Option<String>,
+    pub select_first_volume: bool,
+    pub songs: Vec<SongExplanation>,
+    pub min_distance: Option<f32>,
+    pub threshold: Option<f32>,
+    pub candidates: Vec<usize>,
+    pub pick: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorExplanation {
+    pub name: String,
+    pub status: String,
+    pub value: Option<Value>,
+    pub mood: Option<MoodScores>,
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SongExplanation {
+    pub index: usize,
+    pub title: String,
+    pub relative_path: String,
+    pub distance: f32,
+    pub outcome: SongOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongOutcome {
+    // Not in the volume the coin flip chose
+    OtherVolume,
+    // First volume song dropped by the 70% thinning when the second volume was chosen
+    Thinned,
+    RecentlyPlayed,
+    OutsideThreshold,
+    Candidate,
+}
+
+impl Explanation {
+    pub fn new(timestamp: String, factors: &ExternalFactors, combined_mood: MoodScores) -> Self {
+        Explanation {
+            timestamp,
+            factors: factors
+                .readings
+                .iter()
+                .map(|r| FactorExplanation {
+                    name: r.name.to_string(),
+                    status: r.status.to_string(),
+                    value: r.value.clone(),
+                    mood: r.mood.clone(),
+                    weight: r.weight,
+                })
+                .collect(),
+            combined_mood,
+            first_volume: None,
+            select_first_volume: false,
+            songs: Vec::new(),
+            min_distance: None,
+            threshold: None,
+            candidates: Vec::new(),
+            pick: None,
+        }
+    }
+
+    pub fn picked_song(&self) -> Option<&SongExplanation> {
+        let pick = self.pick?;
+        self.songs.iter().find(|s| s.index == pick)
+    }
+
+    pub fn print(&self) {
+        println!("Selection at {}", self.timestamp);
+
+        println!("\nFactors:");
+        for factor in &self.factors {
+            println!("  {} [{}] weight {}", factor.name, factor.status, factor.weight);
+            if let Some(value) = &factor.value {
+                println!("    value: {}", value);
+            }
+            match &factor.mood {
+                Some(mood) => println!("    mood:  {}", format_mood(mood)),
+                None => println!("    mood:  none"),
+            }
+        }
+        println!("\nCombined mood: {}", format_mood(&self.combined_mood));
+
+        let volume = self.first_volume.as_deref().unwrap_or("none");
+        if self.select_first_volume {
+            println!("Volume coin flip: first volume only ({})", volume);
+        } else {
+            println!("Volume coin flip: other volumes, first volume ({}) thinned", volume);
+        }
+
+        println!("\nSongs:");
+        for song in &self.songs {
+            println!(
+                "  {:>3} {:<28} {:>7.4}  {:?}",
+                song.index, song.title, song.distance, song.outcome
+            );
+        }
+
+        match (self.min_distance, self.threshold) {
+            (Some(min), Some(threshold)) => {
+                println!("\nThreshold: {:.4} (1.2 x min distance {:.4})", threshold, min)
+            }
+            _ => println!("\nThreshold: none, every song was excluded"),
+        }
+
+        let candidates: Vec<&str> = self
+            .songs
+            .iter()
+            .filter(|s| self.candidates.contains(&s.index))
+            .map(|s| s.title.as_str())
+            .collect();
+        println!("Candidates ({}): {}", candidates.len(), candidates.join(", "));
+
+        match self.picked_song() {
+            Some(song) => println!("Pick: {} ({})", song.title, song.relative_path),
+            None => println!("Pick: none"),
+        }
+    }
+}
+
+fn format_mood(mood: &MoodScores) -> String {
+    format!(
+        "happy {:.3}, melancholic {:.3}, hopeful {:.3}, nostalgic {:.3}, mysterious {:.3}, relaxing {:.3}",
+        mood.happy, mood.melancholic, mood.hopeful, mood.nostalgic, mood.mysterious, mood.relaxing
+    )
+}
@@ -1,4 +1,7 @@
+pub mod catalog;
+pub mod explain;
 pub mod song_picker;
+#[allow(clippy::module_inception)]
 mod player;
 
-pub use player::play_song;
\ No newline at end of file
+pub use player::{play_mp3, play_song};
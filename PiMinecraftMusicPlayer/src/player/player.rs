@@ -1,42 +1,78 @@
-use super::song_picker::get_best_song;
-use dotenv::dotenv;
+use super::catalog::load_catalog;
+use super::song_picker::{get_best_song, record_play};
+use crate::config::get_config;
+use crate::external_factors::FactorRegistry;
+use crate::external_factors::get_occupancy::nobody_home;
+use crate::external_factors::weather::{AlertPlayback, alert_playback};
 use std::path::Path;
-use std::process::ExitStatus;
-use std::{env, process::Command};
+use tokio::process::Command;
 
-pub async fn play_song() {
-    dotenv().ok();
+// mpg123's default output scale factor
+const MPG123_FULL_SCALE: f32 = 32768.0;
 
-    let album_path_str: String = env::var("ALBUM_PATH")
-        .expect("SONG_JSON_PATH must be set.")
-        .parse()
-        .unwrap();
-    let path = Path::new(&album_path_str);
+pub async fn play_song() -> bool {
+    let registry = FactorRegistry::builtin(get_config());
+    if nobody_home(&registry).await {
+        println!("Nobody is home, skipping playback");
+        return true;
+    }
+    let volume = match alert_playback(&registry).await {
+        AlertPlayback::Normal => 1.0,
+        AlertPlayback::Soften { volume, event } => {
+            println!("{} in effect, playing at {:.0}% volume", event, volume * 100.0);
+            volume
+        }
+        AlertPlayback::Suppress { event } => {
+            println!("{} in effect, skipping playback", event);
+            return true;
+        }
+    };
+
+    let catalog = match load_catalog() {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            eprintln!("Failed to load song catalog: {}", e);
+            return false;
+        }
+    };
+    catalog.report();
 
-    let best_song = get_best_song().await;
-    let song_path = path.join(best_song).to_str().unwrap().to_string();
+    let Some(pick) = get_best_song(&catalog).await else {
+        return false;
+    };
 
-    play_mp3(&song_path);
+    if !play_mp3_at(&pick.track.path, volume).await {
+        return false;
+    }
+    record_play(&pick);
+    true
 }
 
-pub fn play_mp3(path: &String) {
-    let mpg123: String = env::var("MPG123_PATH")
-        .expect("SONG_JSON_PATH must be set.")
-        .parse()
-        .unwrap();
+pub async fn play_mp3(path: &Path) -> bool {
+    play_mp3_at(path, 1.0).await
+}
 
-    let mut cmd = Command::new(mpg123);
+// Volume is a fraction of mpg123's full output scale. Dropping the future kills mpg123,
+// so the daemon can stop mid-song.
+async fn play_mp3_at(path: &Path, volume: f32) -> bool {
+    let config = get_config();
+    let mut cmd = Command::new(&config.paths.mpg123);
+    cmd.kill_on_drop(true);
 
-    if let Ok(xdg_runtime_dir) = env::var("XDG_RUNTIME_DIR") {
+    if let Some(xdg_runtime_dir) = &config.paths.xdg_runtime_dir {
         cmd.env("XDG_RUNTIME_DIR", xdg_runtime_dir);
     }
+    if volume < 1.0 {
+        cmd.arg("-f").arg(((volume * MPG123_FULL_SCALE) as u32).to_string());
+    }
 
-    let output = cmd
-        .arg("-o")
-        .arg("pulse")
-        .arg(path)
-        .output()
-        .expect("Failed to execute mpg123");
+    let output = match cmd.arg("-o").arg("pulse").arg(path).output().await {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Failed to execute mpg123: {}", e);
+            return false;
+        }
+    };
 
     if !output.status.success() {
         eprintln!("mpg123 exited with status: {}", output.status);
@@ -45,4 +81,5 @@ pub fn play_mp3(path: &String) {
     } else {
         println!("stdout: {}", String::from_utf8_lossy(&output.stdout));
     }
+    output.status.success()
 }
@@ -0,0 +1,115 @@
+use crate::config::get_config;
+use crate::external_factors::get_time;
+use crate::player::{play_mp3, play_song};
+
+use chrono::{DateTime, Days, TimeZone};
+use chrono_tz::Tz;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::signal::unix::{Signal, SignalKind, signal};
+use tokio::time::{Interval, MissedTickBehavior, interval, sleep};
+
+use super::scheduler::sample_play_times;
+
+// Runs forever in the foreground, playing at the sampled times and re-planning at midnight.
+// Returns once SIGTERM or Ctrl-C is received, cutting off any song that is playing so
+// systemd's stop timeout is never reached.
+pub async fn run_daemon() -> bool {
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            eprintln!("Failed to listen for SIGTERM: {}", e);
+            return false;
+        }
+    };
+
+    let config = get_config();
+    let keep_alive_period = Duration::from_secs(config.schedule.keep_alive_minutes as u64 * 60);
+    let mut keep_alive = config.paths.silence.as_ref().map(|_| {
+        let mut keep_alive = interval(keep_alive_period);
+        // Ticks missed during a long song are dropped, not played back to back afterwards
+        keep_alive.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        keep_alive
+    });
+
+    loop {
+        let now = get_time::get_local_dt();
+        let mut pending: VecDeque<DateTime<Tz>> = sample_play_times(now)
+            .into_iter()
+            .filter(|t| *t > now)
+            .collect();
+        let midnight = next_midnight(now);
+
+        println!("Planned {} plays for the rest of today:", pending.len());
+        for t in &pending {
+            println!("  {}", t.format("%H:%M"));
+        }
+
+        loop {
+            let next = pending.front().copied().unwrap_or(midnight);
+            let wait = (next - get_time::get_local_dt())
+                .to_std()
+                .unwrap_or(Duration::ZERO);
+
+            let keep_alive_due = tokio::select! {
+                _ = sleep(wait) => false,
+                _ = tick(&mut keep_alive) => true,
+                reason = stop_requested(&mut sigterm) => {
+                    println!("{}, stopping", reason);
+                    return true;
+                }
+            };
+
+            // Woken at midnight with nothing left to play, plan the new day
+            if !keep_alive_due && pending.pop_front().is_none() {
+                break;
+            }
+
+            let playback = async {
+                if keep_alive_due {
+                    play_silence().await;
+                } else {
+                    play_song().await;
+                }
+            };
+            tokio::select! {
+                _ = playback => {}
+                reason = stop_requested(&mut sigterm) => {
+                    println!("{}, stopping playback", reason);
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+async fn tick(keep_alive: &mut Option<Interval>) {
+    match keep_alive {
+        Some(keep_alive) => {
+            keep_alive.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn stop_requested(sigterm: &mut Signal) -> &'static str {
+    tokio::select! {
+        _ = sigterm.recv() => "SIGTERM received",
+        _ = tokio::signal::ctrl_c() => "Interrupted",
+    }
+}
+
+async fn play_silence() {
+    if let Some(silence) = &get_config().paths.silence {
+        play_mp3(silence).await;
+    }
+}
+
+fn next_midnight(now: DateTime<Tz>) -> DateTime<Tz> {
+    let tomorrow = now.date_naive() + Days::new(1);
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap();
+    now.timezone()
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or(now + chrono::Duration::days(1))
+}
@@ -1,2 +1,6 @@
+pub mod daemon;
+pub mod preview;
 pub mod random_time_generator;
-pub mod scheduler;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod scheduler;
+pub mod systemd;
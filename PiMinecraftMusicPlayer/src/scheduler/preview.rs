@@ -0,0 +1,181 @@
+use crate::config::get_config;
+use crate::external_factors::get_holidays::get_calendar;
+use crate::external_factors::get_quarter::quarter_week;
+use crate::external_factors::get_time;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+use super::random_time_generator::SampledHours;
+use super::scheduler::{min_gap, profile_name, sample_day, to_play_times};
+
+const HISTOGRAM_WIDTH: usize = 50;
+
+// Totals for every simulated day that used one profile
+#[derive(Default)]
+struct ProfileStats {
+    days: u32,
+    plays: u32,
+    outside_allowed: u32,
+    in_quiet_hours: u32,
+    too_close: u32,
+    dropped: u32,
+}
+
+impl ProfileStats {
+    fn add(&mut self, sampled: &SampledHours) {
+        self.days += 1;
+        self.plays += sampled.hours.len() as u32;
+        self.outside_allowed += sampled.outside_allowed;
+        self.in_quiet_hours += sampled.in_quiet_hours;
+        self.too_close += sampled.too_close;
+        self.dropped += sampled.dropped;
+    }
+
+    fn draws(&self) -> u32 {
+        self.plays + self.outside_allowed + self.in_quiet_hours + self.too_close
+    }
+}
+
+// Prints the times `schedule` would install on `date` (today by default), installing nothing
+pub fn preview(date: Option<NaiveDate>, seed: Option<u64>) -> bool {
+    let today = get_time::get_local_dt();
+    let Some(day) = date.map_or(Some(today), |d| day_at(today, d)) else {
+        eprintln!("{} has no valid local time to plan from", date.unwrap_or_default());
+        return false;
+    };
+    let seed = seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let gap = min_gap();
+
+    let profile = profile_name(day);
+    let Some(sampled) = sample_day(day, gap, &mut rng) else {
+        eprintln!("Profile '{}' for {} is not configured", profile, day.weekday());
+        return false;
+    };
+
+    let holiday = get_calendar()
+        .holiday_on(day.date_naive())
+        .map_or(String::new(), |h| format!(", {}", h.name));
+    let quarter = quarter_week(day.date_naive()).map_or(String::new(), |w| format!(", {}", w));
+    println!(
+        "Preview for {}{}{} (profile {}, seed {}):",
+        day.format("%A %Y-%m-%d"),
+        holiday,
+        quarter,
+        profile,
+        seed
+    );
+    let times = to_play_times(day, &sampled.hours);
+    for time in &times {
+        println!("  {}", time.format("%H:%M:%S"));
+    }
+    println!(
+        "{} plays, at least {} minutes apart",
+        times.len(),
+        gap.as_secs().div_ceil(60)
+    );
+    println!(
+        "Rejected {} draws outside the allowed hours, {} in quiet hours and {} too close to another play, dropped {} plays",
+        sampled.outside_allowed, sampled.in_quiet_hours, sampled.too_close, sampled.dropped
+    );
+    true
+}
+
+// Samples `days` days from today and prints per-profile totals and an hourly histogram
+pub fn simulate(days: u32, seed: Option<u64>) -> bool {
+    if days == 0 {
+        eprintln!("--days must be at least 1");
+        return false;
+    }
+    let today = get_time::get_local_dt();
+    let seed = seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let gap = min_gap();
+
+    let mut stats: BTreeMap<String, ProfileStats> = BTreeMap::new();
+    let mut histogram = [0u32; 24];
+    for offset in 0..days {
+        let Some(day) = today.checked_add_days(Days::new(offset as u64)) else {
+            break;
+        };
+        let profile = profile_name(day);
+        let Some(sampled) = sample_day(day, gap, &mut rng) else {
+            eprintln!("Profile '{}' for {} is not configured", profile, day.weekday());
+            return false;
+        };
+
+        for hour in &sampled.hours {
+            histogram[(*hour as usize).min(23)] += 1;
+        }
+        stats.entry(profile.to_string()).or_default().add(&sampled);
+    }
+
+    println!(
+        "Simulated {} days from {} (seed {}), plays at least {} minutes apart",
+        days,
+        today.format("%Y-%m-%d"),
+        seed,
+        gap.as_secs().div_ceil(60)
+    );
+
+    println!("\nProfiles:");
+    for (name, s) in &stats {
+        println!(
+            "  {:<12} {:>4} days  {:>6.2} plays/day  {} dropped",
+            name,
+            s.days,
+            s.plays as f64 / s.days as f64,
+            s.dropped
+        );
+        println!(
+            "  {:<12} quiet hour violations {} of {} draws ({:.1}%), {} outside the allowed hours, {} too close",
+            "",
+            s.in_quiet_hours,
+            s.draws(),
+            percent(s.in_quiet_hours, s.draws()),
+            s.outside_allowed,
+            s.too_close
+        );
+    }
+
+    println!("\nPlays by hour:");
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1);
+    for (hour, count) in histogram.iter().enumerate() {
+        let quiet = get_config()
+            .schedule
+            .quiet_hours
+            .iter()
+            .any(|q| q.contains(hour as f64 + 0.5));
+        let bar = "#".repeat((*count as usize * HISTOGRAM_WIDTH).div_ceil(max as usize));
+        println!(
+            "  {:02} {} {:<width$} {}",
+            hour,
+            if quiet { "q" } else { "|" },
+            bar,
+            count,
+            width = HISTOGRAM_WIDTH
+        );
+    }
+    println!("  (q marks quiet hours)");
+    true
+}
+
+// Noon on `date` in the local timezone, away from any DST change
+fn day_at(today: DateTime<Tz>, date: NaiveDate) -> Option<DateTime<Tz>> {
+    today
+        .timezone()
+        .from_local_datetime(&date.and_hms_opt(12, 0, 0)?)
+        .earliest()
+}
+
+fn percent(part: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
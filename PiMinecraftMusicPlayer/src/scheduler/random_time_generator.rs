@@ -1,27 +1,77 @@
+use crate::config::{MixtureComponent, PlayCount, ScheduleConfig, ScheduleProfile};
 use rand::Rng;
+use rand::distr::weighted::WeightedIndex;
 use rand_distr::{Distribution, Normal};
 
-const WEEKEND_DIST: [f64; 4] = [12.5,20.0,2.6,2.7]; // m0, m1, s0, s1
-const WEEKDAY_DIST: [f64; 4] = [11.5,18.5,2.0,2.0]; // m0, m1, s0, s1
+// Draws per play before giving up on it, only reached when the allowed hours
+// cover a sliver of the profile's distribution
+const MAX_ATTEMPTS: u32 = 1000;
 
-pub fn get_weekday_time() -> f64{
-    return get_time(WEEKDAY_DIST);
+#[derive(Debug, Default)]
+pub struct SampledHours {
+    // Hours of the day (0..24) for the day's plays, unsorted
+    pub hours: Vec<f64>,
+    // Draws thrown away for landing outside 0..24 or the allowed hours
+    pub outside_allowed: u32,
+    // Draws thrown away for landing in quiet hours
+    pub in_quiet_hours: u32,
+    // Draws thrown away for landing within the minimum gap of an earlier play
+    pub too_close: u32,
+    // Plays given up on after MAX_ATTEMPTS rejected draws, in effect merged into the others
+    pub dropped: u32,
 }
 
-pub fn get_weekend_time() -> f64{
-    return get_time(WEEKEND_DIST);
-}
+// Rejection samples the profile's mixture so every hour is one `schedule` allows
+// and no two plays are closer than `min_gap` hours
+pub fn sample_profile(
+    rng: &mut impl Rng,
+    profile: &ScheduleProfile,
+    schedule: &ScheduleConfig,
+    min_gap: f64,
+) -> SampledHours {
+    let mut sampled = SampledHours::default();
 
-fn get_time(dist_config: [f64; 4]) -> f64{
-    return generate_bimodal(dist_config[0], dist_config[1], dist_config[2], dist_config[3]) % 24.0;
+    let count = sample_play_count(rng, profile.plays);
+    for _ in 0..count {
+        let mut accepted = None;
+        for _ in 0..MAX_ATTEMPTS {
+            match sample_mixture(rng, &profile.components) {
+                Some(hour) if !(0.0..24.0).contains(&hour) || !schedule.allowed_hours.contains(hour) => {
+                    sampled.outside_allowed += 1
+                }
+                Some(hour) if schedule.quiet_hours.iter().any(|q| q.contains(hour)) => {
+                    sampled.in_quiet_hours += 1
+                }
+                Some(hour) if sampled.hours.iter().any(|h| (h - hour).abs() < min_gap) => {
+                    sampled.too_close += 1
+                }
+                Some(hour) => {
+                    accepted = Some(hour);
+                    break;
+                }
+                // Degenerate profile, which config validation should have caught
+                None => break,
+            }
+        }
+        match accepted {
+            Some(hour) => sampled.hours.push(hour),
+            None => sampled.dropped += 1,
+        }
+    }
+    sampled
 }
 
-fn generate_bimodal(m0: f64, m1: f64, s0: f64, s1: f64) -> f64 {
-    let mut rng = rand::rng();
-    let x: f64 = rng.random();
-    if x < 0.5 {
-        Normal::new(m0, s0).unwrap().sample(&mut rng)
-    } else {
-        Normal::new(m1, s1).unwrap().sample(&mut rng)
+fn sample_play_count(rng: &mut impl Rng, plays: PlayCount) -> u32 {
+    match plays {
+        PlayCount::Fixed(n) => n,
+        PlayCount::Range(range) => rng.random_range(range.min..=range.max),
     }
-}
\ No newline at end of file
+}
+
+// Picks a component by weight then samples it
+fn sample_mixture(rng: &mut impl Rng, components: &[MixtureComponent]) -> Option<f64> {
+    let index = WeightedIndex::new(components.iter().map(|c| c.weight)).ok()?;
+    let component = &components[index.sample(rng)];
+    let normal = Normal::new(component.mean, component.stddev).ok()?;
+    Some(normal.sample(rng))
+}
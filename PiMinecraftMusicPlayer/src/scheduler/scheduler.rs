@@ -1,106 +1,454 @@
+use crate::config::{SchedulerBackend, get_config};
 use crate::external_factors;
+use crate::external_factors::get_holidays::get_calendar;
+use crate::external_factors::get_quarter::plays_scale;
+use crate::player::catalog::load_catalog;
 
-use chrono::DateTime;
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone, Timelike};
 use chrono_tz::Tz;
+use rand::Rng;
 use external_factors::get_time;
-use std::{env, fmt::format, process::Command};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
 
-use super::random_time_generator::{get_weekend_time, get_weekday_time};
+use super::random_time_generator::{SampledHours, sample_profile};
+use super::systemd::schedule_systemd;
 
-const RESET_COMMAND: &str = "crontab -r";
-const SCHEDULER_TIME: &str = "0 0 * * *";
+const BLOCK_BEGIN: &str = "# BEGIN PiMinecraftMusicPlayer (managed block, edits are overwritten)";
+const BLOCK_END: &str = "# END PiMinecraftMusicPlayer";
 
-fn sample(occurance: i8) -> Vec<f64> {
-    
-    let mut times: Vec<f64> = Vec::new();
+// The holiday profile on days off in the calendar, otherwise the one for that day of the week
+pub fn profile_name(day: DateTime<Tz>) -> &'static str {
+    let days = &get_config().schedule.days;
+    match get_calendar().holiday_on(day.date_naive()) {
+        Some(holiday) if holiday.day_off => &days.holiday,
+        _ => days.profile_name(day.weekday()),
+    }
+}
 
-    let time = get_time::get_pacific_dt();
+// Samples from the profile for that day, with its play count scaled by the phase of the
+// quarter. None if the profile isn't configured.
+pub fn sample_day(day: DateTime<Tz>, min_gap: Duration, rng: &mut impl Rng) -> Option<SampledHours> {
+    let schedule = &get_config().schedule;
+    let mut profile = schedule.profiles.get(profile_name(day))?.clone();
+    profile.plays = profile.plays.scaled(plays_scale(day.date_naive()));
+    Some(sample_profile(rng, &profile, schedule, min_gap.as_secs_f64() / 3600.0))
+}
 
-    for _i in 0..occurance {
-        if get_time::is_weekend(time) {
-            times.push(get_weekend_time());
-        } else {
-            times.push(get_weekday_time());
+// The configured gap, or the longest track plus a minute for mpg123 to start and stop
+pub fn min_gap() -> Duration {
+    let configured = Duration::from_secs(get_config().schedule.min_gap_minutes as u64 * 60);
+    match load_catalog() {
+        Ok(catalog) => catalog
+            .longest_duration()
+            .map_or(configured, |longest| configured.max(longest + Duration::from_secs(60))),
+        Err(e) => {
+            eprintln!("Failed to load the library, spacing plays by the configured gap only: {}", e);
+            configured
         }
+    }
+}
 
+// The day's play times as local datetimes in order
+pub fn sample_play_times(day: DateTime<Tz>) -> Vec<DateTime<Tz>> {
+    let gap = min_gap();
+    let Some(sampled) = sample_day(day, gap, &mut rand::rng()) else {
+        eprintln!("Schedule profile '{}' is not configured, nothing to plan", profile_name(day));
+        return Vec::new();
+    };
+
+    let rejected = sampled.outside_allowed + sampled.in_quiet_hours;
+    if rejected > 0 || sampled.too_close > 0 || sampled.dropped > 0 {
+        println!(
+            "Rejected {} sampled times outside the allowed hours and {} within {} minutes of another play, dropped {} plays",
+            rejected,
+            sampled.too_close,
+            gap.as_secs().div_ceil(60),
+            sampled.dropped
+        );
     }
+    to_play_times(day, &sampled.hours)
+}
 
-    return times
-    
+pub fn to_play_times(day: DateTime<Tz>, hours: &[f64]) -> Vec<DateTime<Tz>> {
+    let mut times: Vec<DateTime<Tz>> = hours
+        .iter()
+        .filter_map(|t| hours_to_datetime(day, *t))
+        .collect();
+    times.sort();
+    times
 }
 
-pub fn schedule_cron() -> bool {
-    let times = sample(12);
-    let mut cron_time: Vec<String> = Vec::new();
-    let cur_time = get_time::get_pacific_dt();
-    for t in times{
-        cron_time.push(time_to_cron(cur_time, t));
+fn hours_to_datetime(day: DateTime<Tz>, time: f64) -> Option<DateTime<Tz>> {
+    if !(0.0..24.0).contains(&time) {
+        return None;
     }
+    let secs = (time * 3600.0) as u32;
+    let naive = day
+        .date_naive()
+        .and_time(NaiveTime::from_num_seconds_from_midnight_opt(secs, 0)?);
+    day.timezone().from_local_datetime(&naive).earliest()
+}
 
-    add_to_crontab(cron_time)
+pub fn schedule_plays() -> bool {
+    match get_config().schedule.backend {
+        SchedulerBackend::Cron => schedule_cron(),
+        SchedulerBackend::Systemd => schedule_systemd(),
+    }
+}
+
+pub fn schedule_cron() -> bool {
+    let now = get_time::get_local_dt();
+    // Run partway through the day, the times already gone would otherwise sit in the block
+    let times = sample_play_times(now).into_iter().filter(|t| *t > now);
+    add_to_crontab(&replan_cron(now), times.map(|t| time_to_cron(&t)).collect())
 }
 
-fn add_to_crontab(cron_time: Vec<String>) -> bool {
-    
-    run_command(&RESET_COMMAND.to_string());
+fn add_to_crontab(replan_time: &str, cron_time: Vec<String>) -> bool {
+    let mut entries = vec![get_cron_job(replan_time, "0")];
+    for t in &cron_time {
+        entries.push(get_cron_job(t, "1"));
+    }
+    match get_keep_alive_job() {
+        Some(keep_alive) => entries.push(keep_alive),
+        None => println!("paths.silence is not set, skipping the keep-alive job"),
+    }
+
+    let existing = match read_crontab() {
+        Ok(existing) => existing,
+        Err(e) => {
+            eprintln!("Failed to read crontab: {}", e);
+            return false;
+        }
+    };
 
-    let scheduler_cron = get_add_cron_job_command(&SCHEDULER_TIME.to_string(), &"0".to_string());
-    if !run_command(&scheduler_cron.to_string()){
+    let updated = match replace_managed_block(&existing, &entries) {
+        Ok(updated) => updated,
+        Err(e) => {
+            eprintln!("Not changing the crontab: {}", e);
+            return false;
+        }
+    };
+    if let Err(e) = write_crontab(&updated) {
+        eprintln!("Failed to install crontab: {}", e);
         return false;
     }
 
-    let mut success = true;
+    println!("Installed {} cron entries:", entries.len());
+    for entry in &entries {
+        println!("  {}", entry);
+    }
+    true
+}
+
+fn get_cron_job(cron_formatted_time: &str, arg: &str) -> String {
+    format!("{} ~/MinecraftMusicPlayer.sh {}", cron_formatted_time, arg)
+}
+
+// Keeps every line outside our block, and drops the unmarked entries older
+// versions installed before they started using markers. Unbalanced markers mean the
+// block was edited by hand, so nothing is guessed and the crontab is left alone.
+fn replace_managed_block(existing: &str, entries: &[String]) -> Result<String, String> {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_block = false;
+
+    for (i, line) in existing.lines().enumerate() {
+        if line.trim() == BLOCK_BEGIN {
+            if in_block {
+                return Err(format!("line {} starts a second managed block", i + 1));
+            }
+            in_block = true;
+        } else if line.trim() == BLOCK_END {
+            if !in_block {
+                return Err(format!("line {} ends a managed block that never began", i + 1));
+            }
+            in_block = false;
+        } else if !in_block {
+            if is_legacy_entry(line) {
+                println!("Removing unmanaged entry from an older version: {}", line);
+                continue;
+            }
+            lines.push(line);
+        }
+    }
+    if in_block {
+        return Err("the managed block has no end marker".to_string());
+    }
 
-    for t in cron_time{
-        let cmd = get_add_cron_job_command(&t, &"1".to_string());
-        success |= run_command(&cmd);
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
     }
 
-    let keep_alive = get_keep_alive_cmd();
-    success |= run_command(&keep_alive);
+    let mut crontab = String::new();
+    for line in lines {
+        crontab.push_str(line);
+        crontab.push('\n');
+    }
+    crontab.push_str(BLOCK_BEGIN);
+    crontab.push('\n');
+    for entry in entries {
+        crontab.push_str(entry);
+        crontab.push('\n');
+    }
+    crontab.push_str(BLOCK_END);
+    crontab.push('\n');
+    Ok(crontab)
+}
 
-    return success;
+// Only the exact lines the old add_to_crontab wrote: the midnight replan, one dated play
+// per time and the five-minute keep-alive. Anything the user changed is theirs.
+fn is_legacy_entry(line: &str) -> bool {
+    let line = line.trim();
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let is_number = |field: &str| !field.is_empty() && field.chars().all(|c| c.is_ascii_digit());
 
+    match fields.as_slice() {
+        ["0", "0", "*", "*", "*", "~/MinecraftMusicPlayer.sh", "0"] => true,
+        [minute, hour, day, month, "*", "~/MinecraftMusicPlayer.sh", "1"] => {
+            [minute, hour, day, month].iter().all(|f| is_number(f))
+        }
+        _ => {
+            line.starts_with("*/5 * * * * ")
+                && line.contains(" -o pulse '")
+                && line.ends_with("' > ~/keep-alive-log.txt 2>&1")
+        }
+    }
 }
 
-fn get_add_cron_job_command(cron_formatted_time: &String, arg: &String) -> String{
+fn read_crontab() -> Result<String, String> {
+    let output = Command::new("crontab")
+        .arg("-l")
+        .output()
+        .map_err(|e| e.to_string())?;
 
-    let cron_command = format!(r#"(crontab -l ; echo "{} ~/MinecraftMusicPlayer.sh {}") | crontab -"#, cron_formatted_time, arg);
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+
+    // A user without a crontab yet is not an error
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("no crontab") {
+        return Ok(String::new());
+    }
+    Err(format!("crontab -l exited with {}: {}", output.status, stderr.trim()))
+}
+
+// A single `crontab -` replaces the whole table at once, so a failure never leaves it half written
+fn write_crontab(contents: &str) -> Result<(), String> {
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
 
-    cron_command
+    child
+        .stdin
+        .take()
+        .ok_or("crontab stdin unavailable")?
+        .write_all(contents.as_bytes())
+        .map_err(|e| e.to_string())?;
 
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "crontab - exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
 }
 
-fn run_command(command: &String) -> bool {
-    let status = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .status().unwrap();
-    return status.success();
+// cron runs in the system timezone, which may differ from location.timezone
+fn time_to_cron(time: &DateTime<Tz>) -> String {
+    let time = time.with_timezone(&Local);
+    format!("{} {} {} {} *", time.minute(), time.hour(), time.day(), time.month())
 }
 
-fn time_to_cron(cur_time: DateTime<Tz>, time: f64) -> String{
-    
-    let day = get_time::get_pacific_day(cur_time);
-    let month = get_time::get_pacific_month(cur_time);
-    let hour: i8 = time as i8;
-    let min: i8 = ((time - (hour as f64)) * 60.0) as i8;
+// Daily at the next midnight in location.timezone, converted like time_to_cron. A zone
+// whose midnight is skipped by a DST change falls back to the system's midnight.
+fn replan_cron(now: DateTime<Tz>) -> String {
+    let midnight = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| now.timezone().from_local_datetime(&day.and_time(NaiveTime::MIN)).earliest());
+    match midnight {
+        Some(midnight) => {
+            let midnight = midnight.with_timezone(&Local);
+            format!("{} {} * * *", midnight.minute(), midnight.hour())
+        }
+        None => "0 0 * * *".to_string(),
+    }
+}
 
-    let cron_str = format!("{} {} {} {} *", min, hour, day, month);
+fn get_keep_alive_job() -> Option<String> {
+    let config = get_config();
+    let silence_path = config.paths.silence.as_ref()?;
 
-    return cron_str;
+    Some(format!(
+        "*/{} * * * * {} -o pulse '{}' > ~/keep-alive-log.txt 2>&1",
+        config.schedule.keep_alive_minutes,
+        config.paths.mpg123.display(),
+        silence_path.display()
+    ))
 }
 
-fn get_keep_alive_cmd() -> String {
-    let mpg123: String = env::var("MPG123_PATH")
-        .expect("SONG_JSON_PATH must be set.")
-        .parse()
-        .unwrap();
+#[cfg(test)]
+mod tests {
+    use super::super::random_time_generator::sample_profile;
+    use super::*;
+    use crate::config::{HourRange, MixtureComponent, PlayCount, ScheduleConfig, ScheduleProfile};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
 
-    let silence_path: String = env::var("SILENCE_PATH")
-        .expect("SONG_JSON_PATH must be set.")
-        .parse()
-        .unwrap();
+    fn block(entries: &[&str]) -> String {
+        let mut block = format!("{}\n", BLOCK_BEGIN);
+        for entry in entries {
+            block.push_str(entry);
+            block.push('\n');
+        }
+        block.push_str(BLOCK_END);
+        block.push('\n');
+        block
+    }
+
+    #[test]
+    fn managed_block_is_replaced_or_appended() {
+        let entries = vec!["0 0 * * * ~/MinecraftMusicPlayer.sh 0".to_string()];
+        let user = "MAILTO=me@example.com\n15 3 * * * ~/backup.sh\n";
+        let cases = [
+            // No crontab yet
+            ("", block(&[entries[0].as_str()])),
+            // No block yet, user lines first
+            (user, format!("{}{}", user, block(&[entries[0].as_str()]))),
+            // An existing block is replaced where the user lines around it survive
+            (
+                &*format!("{}{}# after\n", user, block(&["5 9 18 10 * ~/MinecraftMusicPlayer.sh 1"])),
+                format!("{}# after\n{}", user, block(&[entries[0].as_str()])),
+            ),
+            // Trailing blank lines don't pile up
+            (
+                "15 3 * * * ~/backup.sh\n\n\n",
+                format!("15 3 * * * ~/backup.sh\n{}", block(&[entries[0].as_str()])),
+            ),
+        ];
+        for (existing, expected) in cases {
+            let updated = replace_managed_block(existing, &entries).unwrap();
+            assert_eq!(updated, expected, "{:?}", existing);
+        }
+    }
+
+    #[test]
+    fn unbalanced_markers_are_refused() {
+        let cases = [
+            format!("{}\n15 3 * * * ~/backup.sh\n", BLOCK_BEGIN),
+            format!("15 3 * * * ~/backup.sh\n{}\n", BLOCK_END),
+            format!("{}\n{}", BLOCK_BEGIN, block(&[])),
+        ];
+        for existing in cases {
+            assert!(replace_managed_block(&existing, &[]).is_err(), "{:?}", existing);
+        }
+    }
 
-    format!(r#"(crontab -l ; echo "*/5 * * * * {} -o pulse '{}' > ~/keep-alive-log.txt 2>&1") | crontab -"#, mpg123, silence_path)
-}
\ No newline at end of file
+    #[test]
+    fn only_generated_legacy_lines_are_removed() {
+        let cases = [
+            ("0 0 * * * ~/MinecraftMusicPlayer.sh 0", true),
+            ("42 13 18 10 * ~/MinecraftMusicPlayer.sh 1", true),
+            (
+                "*/5 * * * * /usr/bin/mpg123 -o pulse '/home/pi/silence.mp3' > ~/keep-alive-log.txt 2>&1",
+                true,
+            ),
+            // The user's own lines
+            ("# 0 0 * * * ~/MinecraftMusicPlayer.sh 0", false),
+            ("30 7 * * 1-5 ~/MinecraftMusicPlayer.sh 1", false),
+            ("0 0 * * * ~/MinecraftMusicPlayer.sh 0 >> ~/replan.log", false),
+            (
+                "*/10 * * * * /usr/bin/mpg123 -o pulse '/home/pi/silence.mp3' > ~/keep-alive-log.txt 2>&1",
+                false,
+            ),
+            ("0 8 * * * cat ~/keep-alive-log.txt | mail me", false),
+        ];
+        for (line, legacy) in cases {
+            assert_eq!(is_legacy_entry(line), legacy, "{}", line);
+        }
+
+        let existing = "30 7 * * 1-5 ~/MinecraftMusicPlayer.sh 1\n\
+                        42 13 18 10 * ~/MinecraftMusicPlayer.sh 1\n";
+        let updated = replace_managed_block(existing, &[]).unwrap();
+        assert_eq!(updated, format!("30 7 * * 1-5 ~/MinecraftMusicPlayer.sh 1\n{}", block(&[])));
+    }
+
+    fn profile(components: &[(f64, f64)]) -> ScheduleProfile {
+        ScheduleProfile {
+            plays: PlayCount::Fixed(12),
+            components: components
+                .iter()
+                .map(|&(mean, stddev)| MixtureComponent {
+                    mean,
+                    stddev,
+                    weight: 1.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn hours(start: f64, end: f64) -> HourRange {
+        HourRange { start, end }
+    }
+
+    #[test]
+    fn sampled_times_respect_allowed_hours_quiet_hours_and_the_gap() {
+        let min_gap = 0.5;
+        let default = ScheduleConfig::default();
+        let cases = [
+            // The default quiet hours wrap past midnight, and the late component leans into them
+            (default.clone(), profile(&[(11.5, 2.0), (22.5, 2.0)])),
+            // A narrow allowed range with a quiet lunch and a quiet night wrapping past midnight
+            (
+                ScheduleConfig {
+                    allowed_hours: hours(8.0, 23.5),
+                    quiet_hours: vec![hours(12.0, 13.0), hours(21.5, 1.0)],
+                    ..default.clone()
+                },
+                profile(&[(12.5, 1.5), (21.0, 3.0)]),
+            ),
+            // Allowed hours that wrap past midnight themselves
+            (
+                ScheduleConfig {
+                    allowed_hours: hours(20.0, 2.0),
+                    quiet_hours: vec![hours(23.75, 0.25)],
+                    ..default
+                },
+                profile(&[(23.0, 2.0)]),
+            ),
+        ];
+        let day = at(0, 0);
+
+        for (i, (schedule, profile)) in cases.iter().enumerate() {
+            for seed in 0..50 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let sampled = sample_profile(&mut rng, profile, schedule, min_gap);
+                for hour in &sampled.hours {
+                    assert!(schedule.is_allowed(*hour), "case {} seed {}: {}", i, seed, hour);
+                }
+
+                let times = to_play_times(day, &sampled.hours);
+                assert_eq!(times.len(), sampled.hours.len());
+                for pair in times.windows(2) {
+                    let gap = (pair[1] - pair[0]).num_seconds() as f64 / 3600.0;
+                    // to_play_times truncates to whole seconds
+                    assert!(gap > min_gap - 1.0 / 3600.0, "case {} seed {}: {:?}", i, seed, pair);
+                }
+            }
+        }
+    }
+
+    fn at(hour: u32, min: u32) -> DateTime<Tz> {
+        chrono_tz::America::Los_Angeles
+            .with_ymd_and_hms(2026, 10, 18, hour, min, 0)
+            .unwrap()
+    }
+}
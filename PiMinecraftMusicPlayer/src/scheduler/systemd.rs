@@ -0,0 +1,359 @@
+use crate::config::get_config;
+use crate::external_factors::get_time;
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, io};
+
+use super::scheduler::sample_play_times;
+
+const PLAY_PREFIX: &str = "minecraft-music-play-";
+const REPLAN_UNIT: &str = "minecraft-music-replan";
+const KEEP_ALIVE_UNIT: &str = "minecraft-music-keep-alive";
+
+// What the units run and from where, captured at install time
+pub struct UnitTemplate {
+    pub exec: String,
+    pub working_dir: PathBuf,
+    pub keep_alive: Option<String>,
+    pub keep_alive_minutes: u32,
+    // location.timezone, for the daily replan
+    pub timezone: Tz,
+}
+
+#[derive(Debug, Default)]
+pub struct UnitChanges {
+    // Timer names (without .timer) that should be running after the change
+    pub installed: Vec<String>,
+    // Play timers from an earlier plan, and a keep-alive that is no longer configured,
+    // whose files were deleted
+    pub removed: Vec<String>,
+}
+
+pub fn schedule_systemd() -> bool {
+    let unit_dir = match unit_dir() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("HOME is not set and schedule.systemd_unit_dir is empty");
+            return false;
+        }
+    };
+
+    let template = match current_template() {
+        Ok(template) => template,
+        Err(e) => {
+            eprintln!("Failed to work out the unit command: {}", e);
+            return false;
+        }
+    };
+    let times = sample_play_times(get_time::get_local_dt());
+
+    // Stop old timers before their files disappear, otherwise systemd keeps them loaded
+    let stale = match stale_units(&unit_dir, &template, &times) {
+        Ok(stale) => stale,
+        Err(e) => {
+            eprintln!("Failed to list units in '{}': {}", unit_dir.display(), e);
+            return false;
+        }
+    };
+    for name in &stale {
+        systemctl(&["disable", "--now", &format!("{}.timer", name)]);
+    }
+
+    let changes = match write_units(&unit_dir, &template, &times) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("Failed to write units to '{}': {}", unit_dir.display(), e);
+            return false;
+        }
+    };
+
+    let mut success = systemctl(&["daemon-reload"]);
+    for name in &changes.installed {
+        success &= systemctl(&["enable", "--now", &format!("{}.timer", name)]);
+    }
+
+    for name in &changes.removed {
+        println!("Removed {}", name);
+    }
+    println!(
+        "Installed {} timers in {} ({}):",
+        changes.installed.len(),
+        unit_dir.display(),
+        if success { "ok" } else { "some systemctl calls failed" }
+    );
+    for name in &changes.installed {
+        println!("  {}", name);
+    }
+    success
+}
+
+fn unit_dir() -> Option<PathBuf> {
+    if let Some(dir) = &get_config().schedule.systemd_unit_dir {
+        return Some(dir.clone());
+    }
+    let home = env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".config/systemd/user"))
+}
+
+fn current_template() -> io::Result<UnitTemplate> {
+    let config = get_config();
+    let mut exec = format!("\"{}\"", env::current_exe()?.display());
+    if let Some(source) = &config.source {
+        exec.push_str(&format!(" --config \"{}\"", source.display()));
+    }
+
+    let keep_alive = config.paths.silence.as_ref().map(|silence| {
+        format!(
+            "\"{}\" -o pulse \"{}\"",
+            config.paths.mpg123.display(),
+            silence.display()
+        )
+    });
+
+    Ok(UnitTemplate {
+        exec,
+        working_dir: env::current_dir()?,
+        keep_alive,
+        keep_alive_minutes: config.schedule.keep_alive_minutes,
+        timezone: get_time::local_timezone(),
+    })
+}
+
+fn play_unit_name(time: &DateTime<Tz>) -> String {
+    format!("{}{}", PLAY_PREFIX, time.format("%Y%m%d-%H%M%S"))
+}
+
+fn on_calendar(time: &DateTime<Tz>) -> String {
+    format!("{} {}", time.format("%Y-%m-%d %H:%M:%S"), time.timezone().name())
+}
+
+fn stale_play_units(dir: &Path, times: &[DateTime<Tz>]) -> io::Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let keep: Vec<String> = times.iter().map(play_unit_name).collect();
+
+    let mut stale = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(name) = file_name.strip_suffix(".timer")
+            && name.starts_with(PLAY_PREFIX)
+            && !keep.iter().any(|k| k == name)
+        {
+            stale.push(name.to_string());
+        }
+    }
+    stale.sort();
+    Ok(stale)
+}
+
+// Old play units, plus the keep-alive once paths.silence has been taken out of the config
+fn stale_units(
+    dir: &Path,
+    template: &UnitTemplate,
+    times: &[DateTime<Tz>],
+) -> io::Result<Vec<String>> {
+    let mut stale = stale_play_units(dir, times)?;
+    if template.keep_alive.is_none() && dir.join(format!("{}.timer", KEEP_ALIVE_UNIT)).exists() {
+        stale.push(KEEP_ALIVE_UNIT.to_string());
+    }
+    Ok(stale)
+}
+
+// Writes every unit for the given plan into `dir` and deletes the units stale_units finds.
+// Doesn't touch systemctl, so it can be pointed at any directory.
+pub fn write_units(
+    dir: &Path,
+    template: &UnitTemplate,
+    times: &[DateTime<Tz>],
+) -> io::Result<UnitChanges> {
+    fs::create_dir_all(dir)?;
+    let mut changes = UnitChanges {
+        removed: stale_units(dir, template, times)?,
+        ..Default::default()
+    };
+
+    for name in &changes.removed {
+        for ext in ["timer", "service"] {
+            let path = dir.join(format!("{}.{}", name, ext));
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    for time in times {
+        let name = play_unit_name(time);
+        write_unit(
+            dir,
+            &name,
+            &service_unit("Play a Minecraft song", &format!("{} play", template.exec), template),
+            &timer_unit("Scheduled Minecraft song", &on_calendar(time), false),
+        )?;
+        changes.installed.push(name);
+    }
+
+    write_unit(
+        dir,
+        REPLAN_UNIT,
+        &service_unit(
+            "Plan the day's Minecraft songs",
+            &format!("{} schedule", template.exec),
+            template,
+        ),
+        &timer_unit(
+            "Daily Minecraft song planning",
+            &format!("*-*-* 00:00:00 {}", template.timezone.name()),
+            true,
+        ),
+    )?;
+    changes.installed.push(REPLAN_UNIT.to_string());
+
+    if let Some(keep_alive) = &template.keep_alive {
+        write_unit(
+            dir,
+            KEEP_ALIVE_UNIT,
+            &service_unit("Keep the speaker awake", keep_alive, template),
+            &timer_unit(
+                "Keep the speaker awake",
+                &format!("*:0/{}", template.keep_alive_minutes),
+                false,
+            ),
+        )?;
+        changes.installed.push(KEEP_ALIVE_UNIT.to_string());
+    }
+
+    Ok(changes)
+}
+
+fn write_unit(dir: &Path, name: &str, service: &str, timer: &str) -> io::Result<()> {
+    fs::write(dir.join(format!("{}.service", name)), service)?;
+    fs::write(dir.join(format!("{}.timer", name)), timer)
+}
+
+fn service_unit(description: &str, exec: &str, template: &UnitTemplate) -> String {
+    format!(
+        "[Unit]\nDescription={}\n\n[Service]\nType=oneshot\nWorkingDirectory={}\nExecStart={}\n",
+        description,
+        template.working_dir.display(),
+        exec
+    )
+}
+
+// Only the daily replan catches up after a reboot, missed songs are just skipped
+fn timer_unit(description: &str, on_calendar: &str, persistent: bool) -> String {
+    format!(
+        "[Unit]\nDescription={}\n\n[Timer]\nOnCalendar={}\nPersistent={}\n\n[Install]\nWantedBy=timers.target\n",
+        description, on_calendar, persistent
+    )
+}
+
+fn systemctl(args: &[&str]) -> bool {
+    match Command::new("systemctl").arg("--user").args(args).status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            eprintln!("systemctl --user {} exited with {}", args.join(" "), status);
+            false
+        }
+        Err(e) => {
+            eprintln!("Failed to run systemctl: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn template() -> UnitTemplate {
+        UnitTemplate {
+            exec: "\"/usr/local/bin/player\" --config \"/home/pi/config.toml\"".to_string(),
+            working_dir: PathBuf::from("/home/pi"),
+            keep_alive: Some("\"/usr/bin/mpg123\" -o pulse \"/home/pi/silence.mp3\"".to_string()),
+            keep_alive_minutes: 5,
+            timezone: chrono_tz::America::Los_Angeles,
+        }
+    }
+
+    fn at(hour: u32, min: u32) -> DateTime<Tz> {
+        chrono_tz::America::Los_Angeles
+            .with_ymd_and_hms(2026, 10, 18, hour, min, 0)
+            .unwrap()
+    }
+
+    fn read(dir: &Path, file: &str) -> String {
+        fs::read_to_string(dir.join(file)).unwrap()
+    }
+
+    #[test]
+    fn writes_units_and_replaces_an_earlier_plan() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        let changes = write_units(dir, &template(), &[at(9, 30), at(18, 5)]).unwrap();
+        assert_eq!(
+            changes.installed,
+            [
+                "minecraft-music-play-20261018-093000",
+                "minecraft-music-play-20261018-180500",
+                REPLAN_UNIT,
+                KEEP_ALIVE_UNIT,
+            ]
+        );
+        assert!(changes.removed.is_empty());
+
+        let service = read(dir, "minecraft-music-play-20261018-093000.service");
+        assert!(service.contains(
+            "ExecStart=\"/usr/local/bin/player\" --config \"/home/pi/config.toml\" play\n"
+        ));
+        assert!(service.contains("WorkingDirectory=/home/pi\n"));
+        let timer = read(dir, "minecraft-music-play-20261018-093000.timer");
+        assert!(timer.contains("OnCalendar=2026-10-18 09:30:00 America/Los_Angeles\n"));
+        assert!(timer.contains("Persistent=false\n"));
+        let replan = read(dir, &format!("{}.timer", REPLAN_UNIT));
+        assert!(replan.contains("OnCalendar=*-*-* 00:00:00 America/Los_Angeles\n"));
+        assert!(replan.contains("Persistent=true\n"));
+        assert!(read(dir, &format!("{}.timer", KEEP_ALIVE_UNIT)).contains("OnCalendar=*:0/5\n"));
+
+        let changes = write_units(dir, &template(), &[at(18, 5), at(20, 45)]).unwrap();
+        assert_eq!(changes.removed, ["minecraft-music-play-20261018-093000"]);
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "minecraft-music-keep-alive.service",
+                "minecraft-music-keep-alive.timer",
+                "minecraft-music-play-20261018-180500.service",
+                "minecraft-music-play-20261018-180500.timer",
+                "minecraft-music-play-20261018-204500.service",
+                "minecraft-music-play-20261018-204500.timer",
+                "minecraft-music-replan.service",
+                "minecraft-music-replan.timer",
+            ]
+        );
+
+        // Taking paths.silence out of the config drops the keep-alive
+        let no_keep_alive = UnitTemplate {
+            keep_alive: None,
+            ..template()
+        };
+        let changes = write_units(dir, &no_keep_alive, &[at(20, 45)]).unwrap();
+        assert_eq!(
+            changes.removed,
+            ["minecraft-music-play-20261018-180500", KEEP_ALIVE_UNIT]
+        );
+        assert!(!changes.installed.iter().any(|name| name == KEEP_ALIVE_UNIT));
+        assert!(!dir.join(format!("{}.timer", KEEP_ALIVE_UNIT)).exists());
+        assert!(!dir.join(format!("{}.service", KEEP_ALIVE_UNIT)).exists());
+    }
+}